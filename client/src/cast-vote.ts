@@ -12,6 +12,7 @@ import {
   computeLeaf,
   computeMerkleRoot,
   computeNullifier,
+  computeZeroHashes,
   generateProof,
   bigintToBytes32,
   type VoteInputs,
@@ -30,9 +31,12 @@ const CAST_VOTE_DISCRIMINATOR = Buffer.from([0x14, 0xd4, 0x0f, 0xbd, 0x45, 0xb4,
 function serializeCastVote(
   nullifier: Uint8Array,
   vote: number,
-  proofData: Buffer
+  proofData: Buffer,
+  votersRoot: Uint8Array | null,
+  group: number,
+  weight: bigint
 ): Buffer {
-  const writer = Buffer.alloc(8 + 32 + 1 + 4 + proofData.length);
+  const writer = Buffer.alloc(8 + 32 + 1 + 4 + proofData.length + 33 + 1 + 8);
   let offset = 0;
 
   // Write discriminator
@@ -53,6 +57,24 @@ function serializeCastVote(
   proofData.copy(writer, offset);
   offset += proofData.length;
 
+  // Write voters_root (Option<[u8; 32]>)
+  if (votersRoot === null) {
+    writer.writeUInt8(0, offset);
+    offset += 1;
+  } else {
+    writer.writeUInt8(1, offset);
+    Buffer.from(votersRoot).copy(writer, offset + 1);
+    offset += 33;
+  }
+
+  // Write group (u8)
+  writer.writeUInt8(group, offset);
+  offset += 1;
+
+  // Write weight (u64)
+  writer.writeBigUInt64LE(weight, offset);
+  offset += 8;
+
   return writer.subarray(0, offset);
 }
 
@@ -89,15 +111,40 @@ async function main() {
 
   console.log(`\nVoters Root: ${votersRoot}`);
 
+  // The demo proposal has no registry, so its revocation tree is empty and
+  // the path to the voter's slot is all empty subtrees
+  const zeroHashes = computeZeroHashes();
+  const revocationSiblings = zeroHashes.slice(0, TREE_DEPTH);
+  const revocationRoot = zeroHashes[TREE_DEPTH];
+
+  // The demo proposal has a single voter set, i.e. no voter groups
+  const group = 0;
+  // ...and counts one vote per ballot
+  const weight = 0n;
+
   // Generate ZK proof
   console.log("\nGenerating ZK proof (this may take a moment)...");
   const inputs: VoteInputs = {
     secret: voterSecret,
     proposalId,
     vote,
+    numOptions: 2,
     pathIndices,
     siblings,
     votersRoot,
+    revocationRoot,
+    revocationSiblings,
+    weight,
+    leafWeight: weight,
+    // Single-choice ballot
+    ballotMode: 0,
+    votes: Array(8).fill(0),
+    pointBudget: 0,
+    ballotDigest: 0n,
+    group,
+    revision: 0,
+    sealedVote: 0,
+    salt: 0n,
   };
 
   const proofResult = generateProof(inputs);
@@ -115,7 +162,17 @@ async function main() {
 
   const nullifierBytes = bigintToBytes32(nullifier);
   const [nullifierPda] = PublicKey.findProgramAddressSync(
-    [Buffer.from("nullifier"), proposalPda.toBuffer(), Buffer.from(nullifierBytes)],
+    [
+      Buffer.from("nullifier"),
+      proposalPda.toBuffer(),
+      Buffer.from([group]),
+      Buffer.from(nullifierBytes),
+    ],
+    PROGRAM_ID
+  );
+
+  const [configPda] = PublicKey.findProgramAddressSync(
+    [Buffer.from("config")],
     PROGRAM_ID
   );
 
@@ -127,14 +184,20 @@ async function main() {
   console.log(`\nProof data size: ${proofData.length} bytes`);
 
   // Build instruction
-  const data = serializeCastVote(nullifierBytes, vote, proofData);
+  // Prove against the proposal's own voters_root
+  const data = serializeCastVote(nullifierBytes, vote, proofData, null, group, weight);
   console.log(`Instruction data size: ${data.length} bytes`);
 
   const instruction = new TransactionInstruction({
     keys: [
       { pubkey: proposalPda, isSigner: false, isWritable: true },
       { pubkey: nullifierPda, isSigner: false, isWritable: true },
+      // No prerequisite or registry: Anchor reads the program ID as an absent
+      // optional account
+      { pubkey: PROGRAM_ID, isSigner: false, isWritable: false },
+      { pubkey: PROGRAM_ID, isSigner: false, isWritable: false },
       { pubkey: wallet.publicKey, isSigner: true, isWritable: true },
+      { pubkey: configPda, isSigner: false, isWritable: false },
       { pubkey: SystemProgram.programId, isSigner: false, isWritable: false },
     ],
     programId: PROGRAM_ID,
@@ -21,12 +21,43 @@ const TREE_DEPTH = 20;
 // sha256("global:create_proposal")[0..8]
 const CREATE_PROPOSAL_DISCRIMINATOR = Buffer.from([132, 116, 68, 174, 216, 160, 198, 22]);
 
+// Mirrors the program's ProposalParams struct
+interface ProposalParams {
+  approvalOption: number | null;
+  acceptRecentRoots: boolean;
+  eligibleVoters: bigint | null;
+  voterGroups: VoterGroupConfig[];
+  weighted: boolean;
+  ballotMode: BallotMode;
+  allowRevision: boolean;
+}
+
+// Mirrors the program's BallotMode enum
+type BallotMode =
+  | { kind: "single" }
+  | { kind: "approval" }
+  | { kind: "ranked" }
+  | { kind: "quadratic" }
+  | { kind: "points"; budget: number }
+  | { kind: "encrypted"; key: Uint8Array }
+  | { kind: "commitReveal"; revealEndsAt: bigint }
+  | { kind: "hiddenTally"; revealEndsAt: bigint; tallyKey: Uint8Array };
+
+// Mirrors the program's VoterGroupConfig struct
+interface VoterGroupConfig {
+  votersRoot: Uint8Array;
+  weight: number;
+  eligibleVoters: bigint;
+}
+
 function serializeCreateProposal(
   proposalId: bigint,
   votersRoot: Uint8Array,
   title: string,
   description: string,
-  votingEndsAt: bigint
+  votingEndsAt: bigint,
+  optionLabels: string[],
+  params: ProposalParams
 ): Buffer {
   const writer = Buffer.alloc(1024);
   let offset = 0;
@@ -65,6 +96,96 @@ function serializeCreateProposal(
   endsBuf.copy(writer, offset);
   offset += 8;
 
+  // Write num_options (u8)
+  writer.writeUInt8(optionLabels.length, offset);
+  offset += 1;
+
+  // Write option_labels (Vec<String> = u32 len + strings)
+  writer.writeUInt32LE(optionLabels.length, offset);
+  offset += 4;
+  for (const label of optionLabels) {
+    const labelBuf = Buffer.from(label, "utf8");
+    writer.writeUInt32LE(labelBuf.length, offset);
+    offset += 4;
+    labelBuf.copy(writer, offset);
+    offset += labelBuf.length;
+  }
+
+  // Write params.approval_option (Option<u8>)
+  if (params.approvalOption === null) {
+    writer.writeUInt8(0, offset);
+    offset += 1;
+  } else {
+    writer.writeUInt8(1, offset);
+    writer.writeUInt8(params.approvalOption, offset + 1);
+    offset += 2;
+  }
+
+  // Write params.accept_recent_roots (bool)
+  writer.writeUInt8(params.acceptRecentRoots ? 1 : 0, offset);
+  offset += 1;
+
+  // Write params.eligible_voters (Option<u64>)
+  if (params.eligibleVoters === null) {
+    writer.writeUInt8(0, offset);
+    offset += 1;
+  } else {
+    writer.writeUInt8(1, offset);
+    writer.writeBigUInt64LE(params.eligibleVoters, offset + 1);
+    offset += 9;
+  }
+
+  // Write params.voter_groups (Vec<VoterGroupConfig>)
+  writer.writeUInt32LE(params.voterGroups.length, offset);
+  offset += 4;
+  for (const group of params.voterGroups) {
+    Buffer.from(group.votersRoot).copy(writer, offset);
+    offset += 32;
+    writer.writeUInt32LE(group.weight, offset);
+    offset += 4;
+    writer.writeBigUInt64LE(group.eligibleVoters, offset);
+    offset += 8;
+  }
+
+  // Write params.weighted (bool)
+  writer.writeUInt8(params.weighted ? 1 : 0, offset);
+  offset += 1;
+
+  // Write params.ballot_mode (BallotMode: u8 variant index + fields)
+  const ballotModes = [
+    "single",
+    "approval",
+    "ranked",
+    "quadratic",
+    "points",
+    "encrypted",
+    "commitReveal",
+    "hiddenTally",
+  ];
+  writer.writeUInt8(ballotModes.indexOf(params.ballotMode.kind), offset);
+  offset += 1;
+  if (params.ballotMode.kind === "points") {
+    writer.writeUInt32LE(params.ballotMode.budget, offset);
+    offset += 4;
+  } else if (params.ballotMode.kind === "encrypted") {
+    // Election public key: uncompressed BN254 G1 point
+    Buffer.from(params.ballotMode.key).copy(writer, offset);
+    offset += 64;
+  } else if (params.ballotMode.kind === "commitReveal") {
+    writer.writeBigInt64LE(params.ballotMode.revealEndsAt, offset);
+    offset += 8;
+  } else if (params.ballotMode.kind === "hiddenTally") {
+    writer.writeBigInt64LE(params.ballotMode.revealEndsAt, offset);
+    offset += 8;
+    // X25519 key the ballots' openings are escrowed to
+    Buffer.from(params.ballotMode.tallyKey).copy(writer, offset);
+    offset += 32;
+  }
+
+  // Write params.allow_revision (bool)
+  writer.writeUInt8(params.allowRevision ? 1 : 0, offset);
+  offset += 1;
+
   return writer.subarray(0, offset);
 }
 
@@ -109,6 +230,11 @@ async function main() {
     PROGRAM_ID
   );
 
+  const [configPda] = PublicKey.findProgramAddressSync(
+    [Buffer.from("config")],
+    PROGRAM_ID
+  );
+
   console.log(`\nProposal PDA: ${proposalPda.toBase58()}`);
 
   // Build instruction data
@@ -117,7 +243,17 @@ async function main() {
     bigintToBytes32(votersRoot),
     title,
     description,
-    votingEndsAt
+    votingEndsAt,
+    ["Yes", "No"],
+    {
+      approvalOption: 0,
+      acceptRecentRoots: false,
+      eligibleVoters: BigInt(voterSecrets.length),
+      voterGroups: [],
+      weighted: false,
+      ballotMode: { kind: "single" },
+      allowRevision: false,
+    }
   );
 
   console.log(`Instruction data: ${data.length} bytes`);
@@ -126,6 +262,13 @@ async function main() {
     keys: [
       { pubkey: proposalPda, isSigner: false, isWritable: true },
       { pubkey: wallet.publicKey, isSigner: true, isWritable: true },
+      // No DAO, prerequisite, registry or encrypted tally: Anchor reads the
+      // program ID as an absent optional account
+      { pubkey: PROGRAM_ID, isSigner: false, isWritable: false },
+      { pubkey: PROGRAM_ID, isSigner: false, isWritable: false },
+      { pubkey: PROGRAM_ID, isSigner: false, isWritable: false },
+      { pubkey: PROGRAM_ID, isSigner: false, isWritable: false },
+      { pubkey: configPda, isSigner: false, isWritable: false },
       { pubkey: SystemProgram.programId, isSigner: false, isWritable: false },
     ],
     programId: PROGRAM_ID,
@@ -22,8 +22,9 @@ export function computeLeaf(secret: bigint): bigint {
   return hashTwo(secret, secret);
 }
 
-export function computeNullifier(secret: bigint, proposalId: bigint): bigint {
-  return hashTwo(secret, proposalId);
+/** nullifier = hash(secret, proposal_id, group); group is 0 without voter groups */
+export function computeNullifier(secret: bigint, proposalId: bigint, group = 0n): bigint {
+  return BigInt(poseidon.F.toString(poseidon([secret, proposalId, group])));
 }
 
 export function computeMerkleRoot(
@@ -44,13 +45,49 @@ export function computeMerkleRoot(
   return current;
 }
 
+/** Roots of empty subtrees: index i is the root of an all-zero tree of depth i */
+export function computeZeroHashes(): bigint[] {
+  const zeroHashes = [BigInt(0)];
+  for (let i = 0; i < TREE_DEPTH; i++) {
+    zeroHashes.push(hashTwo(zeroHashes[i], zeroHashes[i]));
+  }
+  return zeroHashes;
+}
+
 export interface VoteInputs {
   secret: bigint;
   proposalId: bigint;
   vote: number;
+  numOptions: number;
   pathIndices: number[];
   siblings: bigint[];
   votersRoot: bigint;
+  // Proposal's revocation root and the path to the voter's (empty) slot
+  revocationRoot: bigint;
+  revocationSiblings: bigint[];
+  // Token weight revealed by the ballot, 0 on unweighted and quadratic proposals
+  weight: bigint;
+  // Weight committed in the voter's leaf: the public weight, or the hidden
+  // credit budget on quadratic proposals
+  leafWeight: bigint;
+  // 0 = single choice, 1 = approval bitmask in vote, 2 = packed ranking in vote,
+  // 3 = quadratic vote vector in votes, 4 = points vector in votes, 5 = encrypted,
+  // 6 = commit-reveal
+  ballotMode: number;
+  // Per-option votes of quadratic and points ballots, 8 zeros otherwise
+  votes: number[];
+  // Points each points ballot splits, 0 in other modes
+  pointBudget: number;
+  // Digest of an encrypted ballot's ciphertexts or the commitment
+  // hash(sealedVote, salt) of a sealed ballot, 0 on plaintext ballots
+  ballotDigest: bigint;
+  // Voter group on multi-group proposals, 0 otherwise
+  group: number;
+  // 0 when a ballot is first cast, n for its n-th revise_vote
+  revision: number;
+  // Opening of a sealed ballot's commitment, 0 in other modes
+  sealedVote: number;
+  salt: bigint;
 }
 
 export interface ProofResult {
@@ -60,7 +97,7 @@ export interface ProofResult {
 }
 
 function writeProverToml(inputs: VoteInputs): void {
-  const nullifier = computeNullifier(inputs.secret, inputs.proposalId);
+  const nullifier = computeNullifier(inputs.secret, inputs.proposalId, BigInt(inputs.group));
 
   const toml = `# Generated vote proof inputs
 secret = "${inputs.secret}"
@@ -68,8 +105,21 @@ proposal_id = "${inputs.proposalId}"
 vote = "${inputs.vote}"
 nullifier = "${nullifier}"
 voters_root = "${inputs.votersRoot}"
+num_options = "${inputs.numOptions}"
+revocation_root = "${inputs.revocationRoot}"
+weight = "${inputs.weight}"
+ballot_mode = "${inputs.ballotMode}"
+votes = [${inputs.votes.map(v => `"${v}"`).join(", ")}]
+point_budget = "${inputs.pointBudget}"
+ballot_digest = "${inputs.ballotDigest}"
+group = "${inputs.group}"
+revision = "${inputs.revision}"
 path_indices = [${inputs.pathIndices.join(", ")}]
 siblings = [${inputs.siblings.map(s => `"${s}"`).join(", ")}]
+revocation_siblings = [${inputs.revocationSiblings.map(s => `"${s}"`).join(", ")}]
+leaf_weight = "${inputs.leafWeight}"
+sealed_vote = "${inputs.sealedVote}"
+salt = "${inputs.salt}"
 `;
 
   fs.writeFileSync(path.join(CIRCUIT_DIR, "Prover.toml"), toml);
@@ -77,7 +127,7 @@ siblings = [${inputs.siblings.map(s => `"${s}"`).join(", ")}]
 
 export function generateProof(inputs: VoteInputs): ProofResult {
   // Compute the nullifier
-  const nullifier = computeNullifier(inputs.secret, inputs.proposalId);
+  const nullifier = computeNullifier(inputs.secret, inputs.proposalId, BigInt(inputs.group));
 
   // Write the Prover.toml
   writeProverToml(inputs);
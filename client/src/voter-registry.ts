@@ -38,8 +38,9 @@ export function computeLeaf(secret: bigint): bigint {
   return hashTwo(secret, secret);
 }
 
-export function computeNullifier(secret: bigint, proposalId: bigint): bigint {
-  return hashTwo(secret, proposalId);
+/** nullifier = hash(secret, proposal_id, group); group is 0 without voter groups */
+export function computeNullifier(secret: bigint, proposalId: bigint, group = 0n): bigint {
+  return BigInt(poseidon.F.toString(poseidon([secret, proposalId, group])));
 }
 
 interface VoterData {
@@ -8,7 +8,7 @@
  * WASM at build time, which causes OOM errors on CI/CD platforms.
  */
 
-import { poseidon2 } from 'poseidon-lite';
+import { poseidon2, poseidon3 } from 'poseidon-lite';
 
 // Type definitions for dynamically imported modules
 type NoirType = InstanceType<typeof import('@noir-lang/noir_js').Noir>;
@@ -86,9 +86,9 @@ function computeLeaf(secret: bigint): bigint {
   return poseidonHash(secret, secret);
 }
 
-// Compute nullifier: hash(secret, proposal_id)
-function computeNullifier(secret: bigint, proposalId: bigint): bigint {
-  return poseidonHash(secret, proposalId);
+// Compute nullifier: hash(secret, proposal_id, group)
+function computeNullifier(secret: bigint, proposalId: bigint, group = BigInt(0)): bigint {
+  return poseidon3([secret, proposalId, group]);
 }
 
 // Build a merkle tree with one voter at leftmost position (for demo)
@@ -110,6 +110,18 @@ function buildMerkleTree(leaf: bigint): {
   return { root: current, siblings, pathIndices };
 }
 
+// Empty revocation tree: every sibling is an empty subtree, so the voter's
+// slot is provably unrevoked. Matches proposals created without a registry.
+function buildEmptyRevocationPath(): { root: bigint; siblings: bigint[] } {
+  const siblings: bigint[] = [];
+  let current = BigInt(0);
+  for (let i = 0; i < TREE_DEPTH; i++) {
+    siblings.push(current);
+    current = poseidonHash(current, current);
+  }
+  return { root: current, siblings };
+}
+
 // Format bigint as hex string
 function toHex(value: bigint): string {
   return '0x' + value.toString(16).padStart(64, '0');
@@ -129,6 +141,10 @@ export interface MerkleProofData {
   votersRoot: string; // Hex string
   siblings: string[]; // Array of 20 hex strings
   pathIndices: number[]; // Array of 20 (0 or 1)
+  // Proposal's revocation_root and the path to the voter's slot; omitted for
+  // proposals without a registry, whose revocation tree is empty
+  revocationRoot?: string; // Hex string
+  revocationSiblings?: string[]; // Array of 20 hex strings
 }
 
 export interface ProofInputs {
@@ -136,6 +152,15 @@ export interface ProofInputs {
   proposalId: number;
   vote: number; // 0 to numOptions-1 (for binary: 0=no, 1=yes)
   numOptions: number; // Number of vote options (2-8, default 2 for binary)
+  ballotMode?: number; // 0 = single choice, 1 = approval bitmask, 2 = packed ranking in vote, 3 = quadratic, 4 = points, 5 = encrypted, 6 = commit-reveal (default 0)
+  weight?: string; // Weight in the voter's leaf on weighted proposals, the credit budget on quadratic ones (default 0)
+  votes?: number[]; // Per-option votes of quadratic and points ballots (default all 0)
+  pointBudget?: number; // Points each points ballot splits (default 0)
+  ballotDigest?: string; // Digest of an encrypted ballot's ciphertexts, or a sealed ballot's commitment (default 0)
+  group?: number; // Voter group on multi-group proposals (default 0)
+  revision?: number; // Revision of the ballot, n for its n-th revise_vote (default 0)
+  sealedVote?: number; // Choice committed by a sealed ballot (default 0)
+  salt?: string; // Salt of a sealed ballot's commitment (default 0)
   merkleProof?: MerkleProofData; // If provided, use actual proof; otherwise demo mode
 }
 
@@ -150,6 +175,14 @@ export interface ProofResult {
       proposalId: string;
       vote: string;
       numOptions: string;
+      revocationRoot: string;
+      weight: string;
+      ballotMode: string;
+      votes: string[];
+      pointBudget: string;
+      ballotDigest: string;
+      group: string;
+      revision: string;
     };
     nullifierBytes: Uint8Array;
   };
@@ -192,26 +225,48 @@ export async function generateVoteProof(
     const proposalId = BigInt(inputs.proposalId);
     const vote = BigInt(inputs.vote);
     const numOptions = BigInt(inputs.numOptions || 2); // Default to binary voting
-
-    // Validate vote is within range
-    if (inputs.vote < 0 || inputs.vote >= (inputs.numOptions || 2)) {
-      throw new Error(`Invalid vote: ${inputs.vote}. Must be 0 to ${(inputs.numOptions || 2) - 1}`);
+    const leafWeight = BigInt(inputs.weight || 0);
+    const ballotMode = BigInt(inputs.ballotMode || 0);
+    // Quadratic ballots keep the credit budget private
+    const weight = ballotMode === BigInt(3) ? BigInt(0) : leafWeight;
+    const votes = (inputs.votes || Array(8).fill(0)).map(v => BigInt(v));
+    const pointBudget = BigInt(inputs.pointBudget || 0);
+    const ballotDigest = BigInt(inputs.ballotDigest || 0);
+    const group = BigInt(inputs.group || 0);
+    const revision = BigInt(inputs.revision || 0);
+    const sealedVote = BigInt(inputs.sealedVote || 0);
+    const salt = BigInt(inputs.salt || 0);
+
+    // Validate vote is within range (or a non-empty mask of the options on approval
+    // ballots); packed rankings and vote vectors are checked by the circuit
+    if (ballotMode < BigInt(2)) {
+      const voteLimit = ballotMode === BigInt(0) ? (inputs.numOptions || 2) : 1 << (inputs.numOptions || 2);
+      if (inputs.vote < (ballotMode === BigInt(0) ? 0 : 1) || inputs.vote >= voteLimit) {
+        throw new Error(`Invalid vote: ${inputs.vote}. Must be below ${voteLimit}`);
+      }
     }
 
     // Compute cryptographic commitments using real Poseidon
-    const leaf = computeLeaf(secret);
-    const nullifier = computeNullifier(secret, proposalId);
+    // Weighted leaves commit the weight alongside the plain commitment
+    const leaf = leafWeight === BigInt(0) ? computeLeaf(secret) : poseidonHash(computeLeaf(secret), leafWeight);
+    const nullifier = computeNullifier(secret, proposalId, group);
 
     // Use provided merkle proof or build demo tree
     let votersRoot: bigint;
     let siblings: bigint[];
     let pathIndices: number[];
+    let revocationRoot: bigint | undefined;
+    let revocationSiblings: bigint[] | undefined;
 
     if (inputs.merkleProof) {
       // Use actual merkle proof from voter registry
       votersRoot = BigInt(inputs.merkleProof.votersRoot);
       siblings = inputs.merkleProof.siblings.map(s => BigInt(s));
       pathIndices = inputs.merkleProof.pathIndices;
+      if (inputs.merkleProof.revocationRoot && inputs.merkleProof.revocationSiblings) {
+        revocationRoot = BigInt(inputs.merkleProof.revocationRoot);
+        revocationSiblings = inputs.merkleProof.revocationSiblings.map(s => BigInt(s));
+      }
       console.log('Using actual merkle proof from voter registry');
     } else {
       // Demo mode: build tree with single voter at leftmost position
@@ -222,6 +277,12 @@ export async function generateVoteProof(
       console.log('Demo mode: using generated merkle tree');
     }
 
+    if (revocationRoot === undefined || revocationSiblings === undefined) {
+      const revocationPath = buildEmptyRevocationPath();
+      revocationRoot = revocationPath.root;
+      revocationSiblings = revocationPath.siblings;
+    }
+
     // Prepare circuit inputs in the format Noir expects
     const circuitInputs = {
       voters_root: votersRoot.toString(),
@@ -229,9 +290,21 @@ export async function generateVoteProof(
       proposal_id: proposalId.toString(),
       vote: vote.toString(),
       num_options: numOptions.toString(),
+      revocation_root: revocationRoot.toString(),
+      weight: weight.toString(),
+      ballot_mode: ballotMode.toString(),
+      votes: votes.map(v => v.toString()),
+      point_budget: pointBudget.toString(),
+      ballot_digest: ballotDigest.toString(),
+      group: group.toString(),
+      revision: revision.toString(),
       secret: secret.toString(),
       path_indices: pathIndices,
       siblings: siblings.map(s => s.toString()),
+      revocation_siblings: revocationSiblings.map(s => s.toString()),
+      leaf_weight: leafWeight.toString(),
+      sealed_vote: sealedVote.toString(),
+      salt: salt.toString(),
     };
 
     inputsMs = performance.now() - startTime;
@@ -284,6 +357,14 @@ export async function generateVoteProof(
           proposalId: inputs.proposalId.toString(),
           vote: inputs.vote.toString(),
           numOptions: (inputs.numOptions || 2).toString(),
+          revocationRoot: toHex(revocationRoot),
+          weight: weight.toString(),
+          ballotMode: ballotMode.toString(),
+          votes: votes.map(v => v.toString()),
+          pointBudget: pointBudget.toString(),
+          ballotDigest: toHex(ballotDigest),
+          group: group.toString(),
+          revision: revision.toString(),
         },
         nullifierBytes: bigintToBytes32(nullifier),
       },
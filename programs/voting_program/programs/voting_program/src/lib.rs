@@ -1,17 +1,119 @@
 use anchor_lang::prelude::*;
+use anchor_lang::solana_program::bpf_loader_upgradeable;
+use anchor_lang::solana_program::program::invoke_signed;
+use anchor_lang::solana_program::sysvar::SysvarId;
+use anchor_lang::system_program;
+use anchor_spl::token::{self, Mint, Token, TokenAccount};
 use groth16_solana::groth16::{Groth16Verifier, Groth16Verifyingkey};
+use solana_bn254::prelude::{alt_bn128_addition, alt_bn128_multiplication};
+use solana_poseidon::{hashv, Endianness, Parameters};
 
 declare_id!("Cug9uBUHFaJXCYHw4K9vMKJdK6cpbRdYnJcGVxCcWXZp");
 
 /// Maximum size of proof data (proof_a: 64 + proof_b: 128 + proof_c: 64 = 256 bytes)
 const MAX_PROOF_SIZE: usize = 512;
 
-/// Number of public inputs: voters_root, nullifier, proposal_id, vote, num_options
-const PUBLIC_INPUT_COUNT: usize = 5;
+/// Number of public inputs: voters_root, nullifier, proposal_id, vote, num_options,
+/// revocation_root, weight, ballot_mode, votes[0..8], point_budget, ballot_digest,
+/// group, revision
+const PUBLIC_INPUT_COUNT: usize = 20;
 
 /// Maximum number of vote options supported (0-7)
 const MAX_VOTE_OPTIONS: u8 = 8;
 
+/// Maximum number of treasury actions a single proposal can carry
+const MAX_PROPOSAL_ACTIONS: u8 = 16;
+
+/// Maximum number of weighted voter groups on a single proposal
+const MAX_VOTER_GROUPS: usize = 4;
+
+/// Maximum number of members of a decryption committee
+const MAX_COMMITTEE_SIZE: usize = 8;
+
+/// Maximum size of a hidden-tally ballot's encrypted opening
+const MAX_ENCRYPTED_OPENING_SIZE: usize = 128;
+
+/// Depth of the on-chain voter registry tree (must match TREE_DEPTH in the circuit)
+const TREE_DEPTH: usize = 20;
+
+/// Number of recent registry roots kept for proofs built against an older root
+const ROOT_HISTORY_SIZE: usize = 32;
+
+/// Maximum depth of a token-holder snapshot tree (2^32 holders)
+const MAX_SNAPSHOT_DEPTH: usize = 32;
+
+/// Metaplex Token Metadata program, owner of NFT metadata accounts
+const METADATA_PROGRAM_ID: Pubkey = pubkey!("metaqbxxUerdq28cj1RbAWkYQm3ybzjb6a8bt518x1s");
+
+/// Value written into a revoked slot of the revocation tree (field element 1)
+const REVOKED_MARKER: [u8; 32] = {
+    let mut marker = [0u8; 32];
+    marker[31] = 1;
+    marker
+};
+
+/// Roots of empty subtrees at each level of the voter registry tree
+/// ZERO_HASHES[0] = 0, ZERO_HASHES[i] = Poseidon(ZERO_HASHES[i-1], ZERO_HASHES[i-1])
+/// (big-endian field elements, circom-compatible Poseidon over BN254)
+const ZERO_HASHES: [[u8; 32]; TREE_DEPTH + 1] = [
+    hex32("0000000000000000000000000000000000000000000000000000000000000000"),
+    hex32("2098f5fb9e239eab3ceac3f27b81e481dc3124d55ffed523a839ee8446b64864"),
+    hex32("1069673dcdb12263df301a6ff584a7ec261a44cb9dc68df067a4774460b1f1e1"),
+    hex32("18f43331537ee2af2e3d758d50f72106467c6eea50371dd528d57eb2b856d238"),
+    hex32("07f9d837cb17b0d36320ffe93ba52345f1b728571a568265caac97559dbc952a"),
+    hex32("2b94cf5e8746b3f5c9631f4c5df32907a699c58c94b2ad4d7b5cec1639183f55"),
+    hex32("2dee93c5a666459646ea7d22cca9e1bcfed71e6951b953611d11dda32ea09d78"),
+    hex32("078295e5a22b84e982cf601eb639597b8b0515a88cb5ac7fa8a4aabe3c87349d"),
+    hex32("2fa5e5f18f6027a6501bec864564472a616b2e274a41211a444cbe3a99f3cc61"),
+    hex32("0e884376d0d8fd21ecb780389e941f66e45e7acce3e228ab3e2156a614fcd747"),
+    hex32("1b7201da72494f1e28717ad1a52eb469f95892f957713533de6175e5da190af2"),
+    hex32("1f8d8822725e36385200c0b201249819a6e6e1e4650808b5bebc6bface7d7636"),
+    hex32("2c5d82f66c914bafb9701589ba8cfcfb6162b0a12acf88a8d0879a0471b5f85a"),
+    hex32("14c54148a0940bb820957f5adf3fa1134ef5c4aaa113f4646458f270e0bfbfd0"),
+    hex32("190d33b12f986f961e10c0ee44d8b9af11be25588cad89d416118e4bf4ebe80c"),
+    hex32("22f98aa9ce704152ac17354914ad73ed1167ae6596af510aa5b3649325e06c92"),
+    hex32("2a7c7c9b6ce5880b9f6f228d72bf6a575a526f29c66ecceef8b753d38bba7323"),
+    hex32("2e8186e558698ec1c67af9c14d463ffc470043c9c2988b954d75dd643f36b992"),
+    hex32("0f57c5571e9a4eab49e2c8cf050dae948aef6ead647392273546249d1c1ff10f"),
+    hex32("1830ee67b5fb554ad5f63d4388800e1cfe78e310697d46e43c9ce36134f72cca"),
+    hex32("2134e76ac5d21aab186c2be1dd8f84ee880a1e46eaf712f9d371b6df22191f3e"),
+];
+
+/// Decode a 64-character hex string into 32 bytes at compile time
+const fn hex32(hex: &str) -> [u8; 32] {
+    const fn nibble(c: u8) -> u8 {
+        match c {
+            b'0'..=b'9' => c - b'0',
+            b'a'..=b'f' => c - b'a' + 10,
+            _ => panic!("invalid hex digit"),
+        }
+    }
+    let bytes = hex.as_bytes();
+    let mut out = [0u8; 32];
+    let mut i = 0;
+    while i < 32 {
+        out[i] = (nibble(bytes[2 * i]) << 4) | nibble(bytes[2 * i + 1]);
+        i += 1;
+    }
+    out
+}
+
+/// BN254 base field modulus p (big-endian), used to negate G1 points
+const BN254_FIELD_MODULUS: [u8; 32] =
+    hex32("30644e72e131a029b85045b68181585d97816a916871ca8d3c208c16d87cfd47");
+
+/// BN254 scalar field order r (big-endian)
+const BN254_SCALAR_ORDER: [u8; 32] =
+    hex32("30644e72e131a029b85045b68181585d2833e84879b9709143e1f593f0000001");
+
+/// G1 generator (1, 2) in the altbn254 syscall encoding (x || y, big-endian)
+const G1_GENERATOR: [u8; 64] = {
+    let mut point = [0u8; 64];
+    point[31] = 1;
+    point[63] = 2;
+    point
+};
+
 // ============================================================================
 // Verifying Key Module
 // ============================================================================
@@ -119,7 +221,9 @@ mod verifying_key {
 
     /// Whether on-chain verification is enabled
     /// When true, proofs are verified on-chain using Solana's altbn254 precompiles (~200k CU).
-    /// NOTE: Temporarily disabled until new VK is generated for multi-choice circuit (5 public inputs)
+    /// NOTE: Temporarily disabled until new VK is generated for the circuit with revocation
+    /// weights, ballot modes, vote vectors, ballot digests, voter groups and
+    /// revisions (20 public inputs)
     pub const VERIFICATION_ENABLED: bool = false;
 }
 
@@ -127,11 +231,449 @@ mod verifying_key {
 pub mod voting_program {
     use super::*;
 
+    /// Create the program-wide config with the guardian that can pause it
+    ///
+    /// Only the program's upgrade authority can initialize the config, and
+    /// every pausable instruction requires it to exist.
+    pub fn initialize_config(ctx: Context<InitializeConfig>, guardian: Pubkey) -> Result<()> {
+        let config = &mut ctx.accounts.config;
+        config.guardian = guardian;
+        config.pending_guardian = None;
+        config.paused = false;
+        config.bump = ctx.bumps.config;
+
+        msg!("Program config initialized, guardian {}", guardian);
+        Ok(())
+    }
+
+    /// Emergency stop: reject voting, proposal creation and treasury actions
+    ///
+    /// Finalization and key management keep working while paused.
+    pub fn pause(ctx: Context<SetPaused>) -> Result<()> {
+        let config = &mut ctx.accounts.config;
+        require!(!config.paused, VotingError::ProgramPaused);
+        config.paused = true;
+
+        emit!(ProgramPaused {
+            guardian: config.guardian,
+        });
+        msg!("Program paused by guardian {}", config.guardian);
+        Ok(())
+    }
+
+    /// Lift an emergency stop
+    pub fn unpause(ctx: Context<SetPaused>) -> Result<()> {
+        let config = &mut ctx.accounts.config;
+        require!(config.paused, VotingError::ProgramNotPaused);
+        config.paused = false;
+
+        emit!(ProgramUnpaused {
+            guardian: config.guardian,
+        });
+        msg!("Program unpaused by guardian {}", config.guardian);
+        Ok(())
+    }
+
+    /// Nominate a new guardian (step 1 of 2)
+    pub fn propose_guardian_transfer(
+        ctx: Context<ProposeGuardianTransfer>,
+        new_guardian: Pubkey,
+    ) -> Result<()> {
+        let config = &mut ctx.accounts.config;
+        propose_transfer(
+            config.key(),
+            config.guardian,
+            &mut config.pending_guardian,
+            new_guardian,
+        );
+        Ok(())
+    }
+
+    /// Accept a pending guardian transfer (step 2 of 2)
+    pub fn accept_guardian_transfer(ctx: Context<AcceptGuardianTransfer>) -> Result<()> {
+        let account = ctx.accounts.config.key();
+        let config: &mut ProgramConfig = &mut ctx.accounts.config;
+        accept_transfer(account, &mut config.guardian, &mut config.pending_guardian)
+    }
+
+    /// Create a DAO with a treasury vault governed by its proposals
+    ///
+    /// The treasury is a system-owned PDA (seeds: ["treasury", dao]) that holds
+    /// SOL directly and acts as owner of the DAO's token accounts. Funds only
+    /// leave it through actions attached to proposals that pass.
+    ///
+    /// `quorum_bps` is the turnout, in basis points of eligible voters, a
+    /// proposal needs before its actions can be executed.
+    pub fn create_dao(
+        ctx: Context<CreateDao>,
+        dao_id: u64,
+        name: String,
+        quorum_bps: u16,
+    ) -> Result<()> {
+        require!(quorum_bps <= 10_000, VotingError::InvalidQuorum);
+
+        let dao = &mut ctx.accounts.dao;
+        dao.dao_id = dao_id;
+        dao.authority = ctx.accounts.authority.key();
+        dao.name = name;
+        dao.quorum_bps = quorum_bps;
+        dao.pending_authority = None;
+        dao.treasury_bump = ctx.bumps.treasury;
+        dao.bump = ctx.bumps.dao;
+
+        msg!(
+            "DAO {} created: {}, treasury {}",
+            dao_id,
+            dao.name,
+            ctx.accounts.treasury.key()
+        );
+        Ok(())
+    }
+
+    /// Create an on-chain voter registry (incremental Poseidon Merkle tree)
+    ///
+    /// The registry starts empty; its root is the root of an all-zero tree of
+    /// depth TREE_DEPTH, matching what the circuit computes for empty slots.
+    /// `eligibility` decides who may add leaves and cannot change afterwards.
+    ///
+    /// A `weighted` registry stores leaves hash(commitment, weight) for
+    /// token-weighted voting. Wallet registrations take the weight from the
+    /// wallet's snapshot balance; under `Eligibility::Authority` the authority
+    /// computes weighted leaves off-chain.
+    ///
+    /// A registry is meant to be shared: one membership set per DAO, with each
+    /// proposal snapshotting the current root at creation (see create_proposal).
+    ///
+    /// Registries start Open. Proposals can only be created once the registry
+    /// is frozen (see freeze_registry), so its root is fixed before voting,
+    /// unless they opt into `accept_recent_roots`.
+    pub fn create_voter_registry(
+        ctx: Context<CreateVoterRegistry>,
+        registry_id: u64,
+        eligibility: Eligibility,
+        weighted: bool,
+    ) -> Result<()> {
+        require!(
+            !weighted
+                || matches!(
+                    eligibility,
+                    Eligibility::Authority | Eligibility::TokenSnapshot { .. }
+                ),
+            VotingError::WeightNotSupported
+        );
+
+        let registry = &mut ctx.accounts.registry;
+        registry.registry_id = registry_id;
+        registry.authority = ctx.accounts.authority.key();
+        registry.eligibility = eligibility;
+        registry.status = RegistryStatus::Open;
+        registry.weighted = weighted;
+        registry.pending_authority = None;
+        registry.next_index = 0;
+        registry.member_count = 0;
+        registry.filled_subtrees.copy_from_slice(&ZERO_HASHES[..TREE_DEPTH]);
+        registry.root = ZERO_HASHES[TREE_DEPTH];
+        let slot = Clock::get()?.slot;
+        registry.root_history = [HistoricalRoot::default(); ROOT_HISTORY_SIZE];
+        registry.root_history[0] = HistoricalRoot {
+            root: registry.root,
+            leaf_count: 0,
+            slot,
+        };
+        registry.last_update_slot = slot;
+        registry.root_history_index = 0;
+        registry.revocation_root = ZERO_HASHES[TREE_DEPTH];
+        registry.revoked_count = 0;
+        registry.bump = ctx.bumps.registry;
+
+        emit!(RegistryCreated {
+            registry: registry.key(),
+            registry_id,
+            authority: registry.authority,
+            eligibility,
+            weighted,
+        });
+        msg!("Voter registry {} created", registry_id);
+        Ok(())
+    }
+
+    /// Insert a voter commitment leaf into the registry tree
+    ///
+    /// `commitment` is the voter's leaf, hash(secret, secret), computed
+    /// off-chain so the secret never leaves the voter's device. The tree is
+    /// hashed on-chain with the sol_poseidon syscall (~20 hashes per insert).
+    ///
+    /// Only registries with `Eligibility::Authority` accept leaves from the
+    /// authority; the others take wallet-bound registrations only.
+    pub fn register_commitment(
+        ctx: Context<RegisterCommitment>,
+        commitment: [u8; 32],
+    ) -> Result<()> {
+        let registry = &mut ctx.accounts.registry;
+        require!(
+            registry.eligibility == Eligibility::Authority,
+            VotingError::WalletRegistrationOnly
+        );
+        let index = register_leaf(registry, commitment)?;
+
+        msg!(
+            "Commitment registered in registry {} at index {}, root {:?}",
+            registry.registry_id,
+            index,
+            registry.root
+        );
+        Ok(())
+    }
+
+    /// Add a wallet to a registry's allowlist
+    pub fn add_to_allowlist(ctx: Context<AddToAllowlist>, wallet: Pubkey) -> Result<()> {
+        require!(
+            ctx.accounts.registry.eligibility == Eligibility::Allowlist,
+            VotingError::NotAllowlistRegistry
+        );
+
+        let entry = &mut ctx.accounts.allowlist_entry;
+        entry.registry = ctx.accounts.registry.key();
+        entry.wallet = wallet;
+        entry.bump = ctx.bumps.allowlist_entry;
+
+        emit!(AllowlistEntryAdded {
+            registry: entry.registry,
+            wallet,
+        });
+
+        msg!(
+            "Wallet {} allowlisted in registry {}",
+            wallet,
+            ctx.accounts.registry.registry_id
+        );
+        Ok(())
+    }
+
+    /// Register the signing wallet's voter commitment
+    ///
+    /// Eligibility is checked against the registry's rule (allowlist entry or
+    /// token snapshot). The commitment is stored in a PDA keyed by wallet, so
+    /// each wallet registers exactly once and anyone can rebuild the tree from
+    /// the registry's VoterCommitment accounts ordered by leaf_index.
+    ///
+    /// `holder_proof` is required for token snapshot registries and proves the
+    /// wallet's balance at the snapshot slot (see tools/token-snapshot).
+    ///
+    /// On weighted registries the inserted leaf is hash(commitment, balance),
+    /// so the voter's weight is fixed by the chain rather than self-declared.
+    pub fn register_wallet_commitment(
+        ctx: Context<RegisterWalletCommitment>,
+        commitment: [u8; 32],
+        holder_proof: Option<HolderProof>,
+    ) -> Result<()> {
+        let wallet = ctx.accounts.wallet.key();
+        let registry = &mut ctx.accounts.registry;
+
+        let balance = match registry.eligibility {
+            Eligibility::Authority => return err!(VotingError::WalletRegistrationDisabled),
+            Eligibility::NftCollection { .. } => return err!(VotingError::NftRegistrationOnly),
+            Eligibility::Allowlist => {
+                // Seeds constraint already ties the entry to this registry and wallet
+                require!(
+                    ctx.accounts.allowlist_entry.is_some(),
+                    VotingError::NotEligible
+                );
+                1
+            }
+            Eligibility::TokenSnapshot {
+                min_amount,
+                holders_root,
+                registration_ends_at,
+                ..
+            } => {
+                require!(
+                    Clock::get()?.unix_timestamp < registration_ends_at,
+                    VotingError::RegistrationClosed
+                );
+                let proof = holder_proof.ok_or(VotingError::NotEligible)?;
+                require!(proof.balance >= min_amount, VotingError::NotEligible);
+                verify_holder_proof(&holders_root, &wallet, &proof)?;
+                proof.balance
+            }
+        };
+
+        let leaf = if registry.weighted {
+            require!(balance > 0, VotingError::NotEligible);
+            weighted_leaf(&commitment, balance)?
+        } else {
+            commitment
+        };
+        let leaf_index = register_leaf(registry, leaf)?;
+
+        let voter_commitment = &mut ctx.accounts.voter_commitment;
+        voter_commitment.registry = registry.key();
+        voter_commitment.wallet = wallet;
+        voter_commitment.commitment = commitment;
+        voter_commitment.leaf = leaf;
+        voter_commitment.leaf_index = leaf_index;
+        voter_commitment.bump = ctx.bumps.voter_commitment;
+
+        msg!(
+            "Wallet {} registered in registry {} at index {}",
+            wallet,
+            registry.registry_id,
+            leaf_index
+        );
+        Ok(())
+    }
+
+    /// Register a commitment for an NFT of the registry's collection
+    ///
+    /// The NFT must carry verified Metaplex collection metadata for the
+    /// registry's collection and sit in a token account owned by `wallet`.
+    /// The commitment PDA is keyed by the NFT mint, so each NFT registers
+    /// exactly once and stays consumed after it is transferred; a wallet
+    /// holding several NFTs registers one commitment per NFT.
+    pub fn register_nft_commitment(
+        ctx: Context<RegisterNftCommitment>,
+        commitment: [u8; 32],
+    ) -> Result<()> {
+        let Eligibility::NftCollection { collection } = ctx.accounts.registry.eligibility else {
+            return err!(VotingError::NotNftRegistry);
+        };
+
+        let nft_mint = &ctx.accounts.nft_mint;
+        require!(
+            nft_mint.decimals == 0 && nft_mint.supply == 1,
+            VotingError::NotEligible
+        );
+        require!(
+            metadata_collection(&ctx.accounts.metadata.try_borrow_data()?)
+                == Some((true, collection)),
+            VotingError::NotEligible
+        );
+
+        let wallet = ctx.accounts.wallet.key();
+        let registry = &mut ctx.accounts.registry;
+        let leaf_index = register_leaf(registry, commitment)?;
+
+        let nft_commitment = &mut ctx.accounts.nft_commitment;
+        nft_commitment.registry = registry.key();
+        nft_commitment.mint = nft_mint.key();
+        nft_commitment.wallet = wallet;
+        nft_commitment.commitment = commitment;
+        nft_commitment.leaf_index = leaf_index;
+        nft_commitment.bump = ctx.bumps.nft_commitment;
+
+        msg!(
+            "NFT {} registered in registry {} at index {}",
+            nft_mint.key(),
+            registry.registry_id,
+            leaf_index
+        );
+        Ok(())
+    }
+
+    /// Revoke the registry leaf at `leaf_index`
+    ///
+    /// Revocations live in a second depth-TREE_DEPTH tree indexed like the
+    /// voters tree, where an empty slot (zero) means "not revoked". Ballots
+    /// prove their own slot is still empty against the proposal's
+    /// revocation_root, so a revoked member can no longer vote on proposals
+    /// created afterwards. Proposals already open keep the root they copied.
+    ///
+    /// `siblings` is the revocation tree path for `leaf_index`, rebuilt
+    /// off-chain from previous revocations; the slot is set to REVOKED_MARKER.
+    /// A secret registered at several indices must be revoked at each of them.
+    pub fn revoke_commitment(
+        ctx: Context<RevokeCommitment>,
+        leaf_index: u64,
+        siblings: [[u8; 32]; TREE_DEPTH],
+    ) -> Result<()> {
+        let registry = &mut ctx.accounts.registry;
+        require!(
+            registry.status != RegistryStatus::Archived,
+            VotingError::RegistryArchived
+        );
+        require!(
+            leaf_index < registry.next_index,
+            VotingError::LeafNotRegistered
+        );
+
+        // Slot must currently be empty under the stored root
+        require!(
+            compute_merkle_root(&[0u8; 32], leaf_index, &siblings)? == registry.revocation_root,
+            VotingError::RevocationPathMismatch
+        );
+        registry.revocation_root = compute_merkle_root(&REVOKED_MARKER, leaf_index, &siblings)?;
+        registry.revoked_count = registry
+            .revoked_count
+            .checked_add(1)
+            .ok_or(VotingError::RegistryCountOverflow)?;
+        registry.member_count = registry
+            .member_count
+            .checked_sub(1)
+            .ok_or(VotingError::RegistryCountOverflow)?;
+        registry.last_update_slot = Clock::get()?.slot;
+
+        emit!(CommitmentRevoked {
+            registry: registry.key(),
+            leaf_index,
+            revocation_root: registry.revocation_root,
+            member_count: registry.member_count,
+            slot: registry.last_update_slot,
+        });
+
+        msg!(
+            "Leaf {} revoked in registry {}, revocation root {:?}",
+            leaf_index,
+            registry.registry_id,
+            registry.revocation_root
+        );
+        Ok(())
+    }
+
+    /// Close registration and fix the registry root (Open -> Frozen)
+    ///
+    /// From here on the tree accepts no new leaves, so voters can check that
+    /// their leaf is in the final root before proposals are created against
+    /// it. Revocations are still accepted.
+    pub fn freeze_registry(ctx: Context<SetRegistryStatus>) -> Result<()> {
+        set_registry_status(
+            &mut ctx.accounts.registry,
+            RegistryStatus::Open,
+            RegistryStatus::Frozen,
+        )
+    }
+
+    /// Retire a frozen registry (Frozen -> Archived)
+    ///
+    /// Archived registries accept no revocations and no new proposals;
+    /// proposals already created against them are unaffected.
+    pub fn archive_registry(ctx: Context<SetRegistryStatus>) -> Result<()> {
+        set_registry_status(
+            &mut ctx.accounts.registry,
+            RegistryStatus::Frozen,
+            RegistryStatus::Archived,
+        )
+    }
+
     /// Initialize a new proposal with a voters merkle root
     ///
     /// # Multi-choice voting
     /// - `num_options`: Number of vote options (2-8)
     /// - `option_labels`: Optional labels for each option (e.g., ["Yes", "No"] or ["A", "B", "C", "D"])
+    ///
+    /// Passing a `dao` links the proposal to that DAO's treasury so actions can
+    /// be attached to its options. Only the DAO authority can do this.
+    ///
+    /// Passing a `prerequisite` proposal blocks voting and action execution
+    /// until that proposal is finalized as Succeeded with its approval option
+    /// winning.
+    ///
+    /// Passing a `registry` snapshots its current root as the proposal's
+    /// voters_root; `voters_root` must equal that root so the creator knows
+    /// exactly which membership set the proposal is bound to. The registry
+    /// must be Frozen, or be a token snapshot whose registration window has
+    /// closed, unless `accept_recent_roots` is set. The registry's revocation
+    /// root is copied as well; without a registry nothing is revoked.
+    #[allow(clippy::too_many_arguments)]
     pub fn create_proposal(
         ctx: Context<CreateProposal>,
         proposal_id: u64,
@@ -141,6 +683,7 @@ pub mod voting_program {
         voting_ends_at: i64,
         num_options: u8,
         option_labels: Vec<String>,
+        params: ProposalParams,
     ) -> Result<()> {
         // Validate num_options
         require!(num_options >= 2, VotingError::TooFewOptions);
@@ -149,6 +692,119 @@ pub mod voting_program {
             option_labels.len() == num_options as usize,
             VotingError::OptionLabelsMismatch
         );
+        require!(
+            params
+                .approval_option
+                .is_none_or(|option| option < num_options),
+            VotingError::InvalidApprovalOption
+        );
+
+        require!(
+            params.voter_groups.len() <= MAX_VOTER_GROUPS,
+            VotingError::TooManyVoterGroups
+        );
+        require!(
+            params.ballot_mode != BallotMode::Ranked || params.voter_groups.is_empty(),
+            VotingError::VoterGroupsNotSupported
+        );
+        require!(
+            params.ballot_mode != BallotMode::Quadratic || params.weighted,
+            VotingError::CreditBudgetRequired
+        );
+        if let BallotMode::Points { budget } = params.ballot_mode {
+            require!(budget > 0, VotingError::InvalidPointBudget);
+        }
+        if let BallotMode::Encrypted { key } = params.ballot_mode {
+            require!(
+                !params.weighted && params.voter_groups.is_empty(),
+                VotingError::EncryptedBallotsNotSupported
+            );
+            // The syscall rejects points that are not on the curve; all zeros
+            // leaves the key to a decryption committee
+            g1_add(&key, &[0u8; 64]).map_err(|_| VotingError::InvalidEncryptionKey)?;
+        }
+        require!(
+            ctx.accounts.encrypted_tally.is_some() == params.ballot_mode.is_encrypted(),
+            VotingError::EncryptedTallyMismatch
+        );
+        if let Some(reveal_ends_at) = params.ballot_mode.reveal_ends_at() {
+            require!(
+                reveal_ends_at > voting_ends_at,
+                VotingError::InvalidRevealWindow
+            );
+        }
+        require!(
+            !params.allow_revision
+                || matches!(params.ballot_mode, BallotMode::Single | BallotMode::Approval)
+                || params.ballot_mode.is_vector(),
+            VotingError::RevisionNotSupported
+        );
+
+        let eligible_voters = if !params.voter_groups.is_empty() {
+            require!(
+                ctx.accounts.registry.is_none(),
+                VotingError::VoterGroupsWithRegistry
+            );
+            require!(
+                params.voter_groups[0].voters_root == voters_root,
+                VotingError::VoterGroupRootMismatch
+            );
+            let mut count = 0u64;
+            for group in &params.voter_groups {
+                require!(group.weight > 0, VotingError::InvalidGroupWeight);
+                require!(
+                    group.eligible_voters > 0,
+                    VotingError::EligibleVotersRequired
+                );
+                count = count
+                    .checked_add(group.eligible_voters)
+                    .ok_or(VotingError::TallyOverflow)?;
+            }
+            require!(
+                params.eligible_voters.is_none_or(|eligible| eligible == count),
+                VotingError::EligibleVotersMismatch
+            );
+            count
+        } else if let Some(registry) = &ctx.accounts.registry {
+            require!(
+                registry.root == voters_root,
+                VotingError::RegistryRootMismatch
+            );
+            require!(
+                params.eligible_voters.is_none_or(|count| count == registry.member_count),
+                VotingError::EligibleVotersMismatch
+            );
+            require!(
+                registry.status != RegistryStatus::Archived,
+                VotingError::RegistryArchived
+            );
+            require!(
+                params.accept_recent_roots || registry.is_frozen(Clock::get()?.unix_timestamp),
+                VotingError::RegistryNotFrozen
+            );
+            require!(
+                registry.weighted == params.weighted,
+                VotingError::WeightModeMismatch
+            );
+            registry.member_count
+        } else {
+            require!(!params.accept_recent_roots, VotingError::RegistryRequired);
+            let count = params.eligible_voters.ok_or(VotingError::EligibleVotersRequired)?;
+            require!(count > 0, VotingError::EligibleVotersRequired);
+            count
+        };
+
+        // A prerequisite that already failed can never unblock this proposal
+        if let Some(prerequisite) = &ctx.accounts.prerequisite {
+            require!(
+                prerequisite.approval_option.is_some(),
+                VotingError::PrerequisiteWithoutApprovalOption
+            );
+            require!(
+                !prerequisite.is_finalized || prerequisite.passed(),
+                VotingError::PrerequisiteNotPassed
+            );
+        }
 
         let proposal = &mut ctx.accounts.proposal;
         proposal.proposal_id = proposal_id;
@@ -160,21 +816,130 @@ pub mod voting_program {
         proposal.vote_counts = [0u64; 8]; // Initialize all counts to 0
         proposal.voting_ends_at = voting_ends_at;
         proposal.is_finalized = false;
+        proposal.outcome = ProposalOutcome::Pending;
+        proposal.winning_option = 0;
+        proposal.approval_option = params.approval_option;
+        proposal.dao = ctx.accounts.dao.as_ref().map(|dao| dao.key());
+        proposal.action_count = 0;
+        proposal.pending_authority = None;
+        proposal.prerequisite = ctx
+            .accounts
+            .prerequisite
+            .as_ref()
+            .map(|prerequisite| prerequisite.key());
+        proposal.registry = ctx.accounts.registry.as_ref().map(|registry| registry.key());
+        proposal.snapshot_leaf_count = ctx
+            .accounts
+            .registry
+            .as_ref()
+            .map_or(0, |registry| registry.next_index);
+        proposal.accept_recent_roots = params.accept_recent_roots;
+        proposal.eligible_voters = eligible_voters;
+        proposal.total_votes = 0;
+        proposal.unrevealed_votes = 0;
+        proposal.turnout_bps = 0;
+        proposal.weighted = params.weighted;
+        proposal.ballot_mode = params.ballot_mode;
+        proposal.allow_revision = params.allow_revision;
+        proposal.irv = IrvTabulation::default();
+        proposal.committee = None;
+        proposal.voter_groups = params
+            .voter_groups
+            .iter()
+            .map(|group| VoterGroup {
+                voters_root: group.voters_root,
+                weight: group.weight,
+                eligible_voters: group.eligible_voters,
+                vote_counts: [0u64; 8],
+            })
+            .collect();
+        proposal.revocation_root = ctx
+            .accounts
+            .registry
+            .as_ref()
+            .map_or(ZERO_HASHES[TREE_DEPTH], |registry| registry.revocation_root);
+        proposal.token_snapshot =
+            ctx.accounts
+                .registry
+                .as_ref()
+                .and_then(|registry| match registry.eligibility {
+                    Eligibility::TokenSnapshot { mint, slot, .. } => {
+                        Some(TokenSnapshot { mint, slot })
+                    }
+                    _ => None,
+                });
         proposal.bump = ctx.bumps.proposal;
 
-        // Store option labels (up to 8, max 32 chars each)
-        for (i, label) in option_labels.iter().enumerate() {
-            if i < 8 {
-                proposal.option_labels[i] = label.clone();
-            }
+        if let Some(encrypted_tally) = ctx.accounts.encrypted_tally.as_mut() {
+            encrypted_tally.proposal = proposal.key();
+            encrypted_tally.ciphertexts = [[0u8; 128]; 8];
+            encrypted_tally.decrypted = false;
+            encrypted_tally.bump = ctx.bumps.encrypted_tally.unwrap();
         }
 
+        // Store option labels (one per option, max 32 chars each)
+        proposal.option_labels = option_labels;
+
         msg!(
-            "Proposal {} created: {} options, voters_root {:?}",
+            "Proposal {} created: {} options ({:?} ballots), voters_root {:?}",
             proposal_id,
             num_options,
+            params.ballot_mode,
             voters_root
         );
+        if !proposal.voter_groups.is_empty() {
+            msg!(
+                "{} voter groups, {} eligible voters",
+                proposal.voter_groups.len(),
+                eligible_voters
+            );
+        }
+        Ok(())
+    }
+
+    /// Attach a treasury action to one of a DAO proposal's options
+    ///
+    /// The action runs from the DAO treasury once the proposal is finalized
+    /// with `option` as the winner. Actions can only be added before the first
+    /// vote is cast, so every voter sees the full payload of each option.
+    pub fn add_proposal_action(
+        ctx: Context<AddProposalAction>,
+        option: u8,
+        action: Action,
+    ) -> Result<()> {
+        let proposal = &mut ctx.accounts.proposal;
+
+        require!(proposal.dao.is_some(), VotingError::ProposalHasNoDao);
+        require!(!proposal.is_finalized, VotingError::ProposalFinalized);
+        let clock = Clock::get()?;
+        require!(
+            clock.unix_timestamp < proposal.voting_ends_at,
+            VotingError::VotingEnded
+        );
+        require!(!proposal.has_votes(), VotingError::VotingStarted);
+        require!(option < proposal.num_options, VotingError::InvalidActionOption);
+        require!(
+            proposal.action_count < MAX_PROPOSAL_ACTIONS,
+            VotingError::TooManyActions
+        );
+        action.validate()?;
+
+        let proposal_action = &mut ctx.accounts.action;
+        proposal_action.proposal = proposal.key();
+        proposal_action.index = proposal.action_count;
+        proposal_action.option = option;
+        proposal_action.action = action;
+        proposal_action.executed = false;
+        proposal_action.bump = ctx.bumps.action;
+
+        proposal.action_count += 1;
+
+        msg!(
+            "Action {} attached to proposal {} option {}",
+            proposal_action.index,
+            proposal.proposal_id,
+            option
+        );
         Ok(())
     }
 
@@ -183,270 +948,3608 @@ pub mod voting_program {
     /// The proof proves:
     /// 1. Voter is in the voters_root Merkle tree (membership)
     /// 2. Nullifier is correctly derived from secret + proposal_id
-    /// 3. Vote is valid (0 to num_options-1)
+    /// 3. Vote is valid (0 to num_options-1, or a non-empty bitmask of
+    ///    num_options bits on approval proposals)
     ///
     /// Proof format (256 bytes total):
     /// - proof_a: [u8; 64] - G1 point (negated, big-endian)
     /// - proof_b: [u8; 128] - G2 point (big-endian)
     /// - proof_c: [u8; 64] - G1 point (big-endian)
+    ///
+    /// `voters_root` is the root the proof was built against. `None` means the
+    /// proposal's own voters_root; an older registry root is only accepted by
+    /// proposals created with `accept_recent_roots`.
+    ///
+    /// `group` selects the voter group the proof targets on multi-group
+    /// proposals (0 otherwise). The nullifier is hash(secret, proposal_id,
+    /// group) and the group is a public input, so a member of two groups can
+    /// vote once in each but a nullifier can't be replayed in another group.
+    ///
+    /// `weight` is the weight committed in the voter's leaf on weighted
+    /// proposals (0 otherwise); it is a public input of the proof and is added
+    /// to the chosen option's count.
     pub fn cast_vote(
         ctx: Context<CastVote>,
         nullifier: [u8; 32],
-        vote: u8, // 0 to num_options-1
+        vote: u8, // 0 to num_options-1, or an option bitmask for approval ballots
         proof_data: Vec<u8>,
+        voters_root: Option<[u8; 32]>,
+        group: u8,
+        weight: u64,
     ) -> Result<()> {
         let proposal = &mut ctx.accounts.proposal;
         let nullifier_account = &mut ctx.accounts.nullifier_account;
 
-        // Check voting is still open
-        let clock = Clock::get()?;
-        require!(
-            clock.unix_timestamp < proposal.voting_ends_at,
-            VotingError::VotingEnded
-        );
-        require!(!proposal.is_finalized, VotingError::ProposalFinalized);
+        // Validate vote value and expand it to the options it counts toward
+        let selected = proposal
+            .ballot_mode
+            .selected_options(vote, proposal.num_options)?;
 
-        // Validate vote value (multi-choice: 0 to num_options-1)
-        require!(vote < proposal.num_options, VotingError::InvalidVote);
-
-        // Validate proof size
-        require!(proof_data.len() <= MAX_PROOF_SIZE, VotingError::ProofTooLarge);
-
-        // On-chain ZK proof verification
-        if verifying_key::VERIFICATION_ENABLED {
-            verify_groth16_proof(
-                &proof_data,
-                &proposal.voters_root,
-                &nullifier,
-                proposal.proposal_id,
-                vote,
-                proposal.num_options,
-            )?;
-            msg!("ZK proof verified on-chain (~200k CU)");
-        } else {
-            // When verification is disabled, proofs are checked off-chain
-            // The nullifier PDA still prevents double voting
-            msg!(
-                "Proof received: {} bytes (off-chain verification mode)",
-                proof_data.len()
-            );
-        }
+        admit_ballot(
+            proposal,
+            ctx.accounts.prerequisite.as_ref(),
+            ctx.accounts.registry.as_deref(),
+            &BallotSubmission {
+                nullifier,
+                vote: vote as u32,
+                votes: [0u32; 8],
+                proof_data: &proof_data,
+                voters_root,
+                group,
+                weight,
+                ballot_digest: [0u8; 32],
+                revision: 0,
+            },
+        )?;
 
         // Mark nullifier as used (prevents double voting regardless of verification mode)
         nullifier_account.nullifier = nullifier;
         nullifier_account.proposal = proposal.key();
+        nullifier_account.group = group;
         nullifier_account.bump = ctx.bumps.nullifier_account;
 
-        // Record vote (multi-choice)
-        proposal.vote_counts[vote as usize] = proposal
-            .vote_counts[vote as usize]
-            .checked_add(1)
-            .unwrap();
-
-        // Get option label for logging
-        let option_label = if !proposal.option_labels[vote as usize].is_empty() {
-            proposal.option_labels[vote as usize].clone()
+        // Record vote (multi-choice); multi-group proposals tally per group
+        // and combine by weight at finalization
+        let vote_counts = if proposal.voter_groups.is_empty() {
+            &mut proposal.vote_counts
         } else {
-            format!("Option {}", vote)
+            &mut proposal.voter_groups[group as usize].vote_counts
         };
+        for (option, count) in vote_counts.iter_mut().enumerate() {
+            if selected & (1 << option) != 0 {
+                *count = count
+                    .checked_add(weight.max(1))
+                    .ok_or(VotingError::TallyOverflow)?;
+                nullifier_account.counts[option] = weight.max(1);
+            }
+        }
+        proposal.total_votes = proposal
+            .total_votes
+            .checked_add(1)
+            .ok_or(VotingError::TallyOverflow)?;
+
+        // Get option labels for logging
+        let option_label = (0..proposal.num_options)
+            .filter(|option| selected & (1 << option) != 0)
+            .map(|option| proposal.option_label(option))
+            .collect::<Vec<_>>()
+            .join(", ");
 
         msg!(
-            "Vote cast on proposal {}: {} (option {})",
+            "Vote cast on proposal {}: {} (vote {}, group {}, weight {})",
             proposal.proposal_id,
             option_label,
-            vote
+            vote,
+            group,
+            weight.max(1)
         );
 
         Ok(())
     }
 
-    /// Finalize voting and lock results (multi-choice)
-    pub fn finalize_proposal(ctx: Context<FinalizeProposal>) -> Result<()> {
+    /// Cast a ranked ballot on a `BallotMode::Ranked` proposal
+    ///
+    /// `ranking` lists between 1 and num_options distinct options, most
+    /// preferred first. It is packed into a u32, one option per nibble with
+    /// 0xF after the last ranked option, and that packed value is the proof's
+    /// `vote` public input. The ballot is stored in a RankedBallot account
+    /// (seeds: ["ballot", proposal, nullifier]) for tabulate_irv; the first
+    /// preference is also added to vote_counts as a running tally.
+    pub fn cast_ranked_vote(
+        ctx: Context<CastRankedVote>,
+        nullifier: [u8; 32],
+        ranking: Vec<u8>,
+        proof_data: Vec<u8>,
+        voters_root: Option<[u8; 32]>,
+        weight: u64,
+    ) -> Result<()> {
         let proposal = &mut ctx.accounts.proposal;
-
-        let clock = Clock::get()?;
         require!(
-            clock.unix_timestamp >= proposal.voting_ends_at,
-            VotingError::VotingNotEnded
+            proposal.ballot_mode == BallotMode::Ranked,
+            VotingError::NotRankedProposal
         );
-        require!(!proposal.is_finalized, VotingError::ProposalFinalized);
+        let packed = pack_ranking(&ranking, proposal.num_options)?;
 
-        proposal.is_finalized = true;
+        admit_ballot(
+            proposal,
+            ctx.accounts.prerequisite.as_ref(),
+            ctx.accounts.registry.as_deref(),
+            &BallotSubmission {
+                nullifier,
+                vote: packed,
+                votes: [0u32; 8],
+                proof_data: &proof_data,
+                voters_root,
+                group: 0,
+                weight,
+                ballot_digest: [0u8; 32],
+                revision: 0,
+            },
+        )?;
 
-        // Find winning option(s) for multi-choice voting
-        let mut max_votes = 0u64;
-        let mut winning_option: u8 = 0;
-        let mut total_votes = 0u64;
+        let nullifier_account = &mut ctx.accounts.nullifier_account;
+        nullifier_account.nullifier = nullifier;
+        nullifier_account.proposal = proposal.key();
+        nullifier_account.group = 0;
+        nullifier_account.bump = ctx.bumps.nullifier_account;
 
-        for i in 0..proposal.num_options as usize {
-            total_votes += proposal.vote_counts[i];
-            if proposal.vote_counts[i] > max_votes {
-                max_votes = proposal.vote_counts[i];
-                winning_option = i as u8;
-            }
-        }
+        let ballot = &mut ctx.accounts.ballot;
+        ballot.proposal = proposal.key();
+        ballot.ranking = packed;
+        ballot.weight = weight.max(1);
+        ballot.rounds_counted = 0;
+        ballot.bump = ctx.bumps.ballot;
 
-        // Check for ties
-        let mut tie_count = 0;
-        for i in 0..proposal.num_options as usize {
-            if proposal.vote_counts[i] == max_votes {
-                tie_count += 1;
-            }
-        }
+        let first = ranking[0] as usize;
+        proposal.vote_counts[first] = proposal.vote_counts[first]
+            .checked_add(ballot.weight)
+            .ok_or(VotingError::TallyOverflow)?;
+        proposal.total_votes = proposal
+            .total_votes
+            .checked_add(1)
+            .ok_or(VotingError::TallyOverflow)?;
+
+        msg!(
+            "Ranked vote cast on proposal {}: {} options ranked, first choice {}",
+            proposal.proposal_id,
+            ranking.len(),
+            proposal.option_label(ranking[0])
+        );
+        Ok(())
+    }
+
+    /// Cast a ballot that spreads votes across options (`BallotMode::Quadratic`
+    /// or `BallotMode::Points`)
+    ///
+    /// `votes[i]` is the number of votes (or points) for option i; entries
+    /// from num_options on must be zero and at least one vote must be cast.
+    /// They are public inputs of the proof. On quadratic proposals the proof
+    /// shows that sum(votes[i]^2) fits the credit budget committed in the
+    /// voter's weighted leaf, which is never revealed. On points proposals the
+    /// entries must add up to the proposal's point budget.
+    ///
+    /// Each entry is added to vote_counts, multiplied by `weight` on weighted
+    /// points proposals (where it is the leaf's weight, and 0 otherwise).
+    pub fn cast_vector_vote(
+        ctx: Context<CastVectorVote>,
+        nullifier: [u8; 32],
+        votes: [u32; 8],
+        proof_data: Vec<u8>,
+        voters_root: Option<[u8; 32]>,
+        group: u8,
+        weight: u64,
+    ) -> Result<()> {
+        let proposal = &mut ctx.accounts.proposal;
+        require!(
+            proposal.ballot_mode.is_vector(),
+            VotingError::NotVectorProposal
+        );
+        proposal.validate_vote_vector(&votes)?;
 
-        let result = if tie_count > 1 {
-            "TIE".to_string()
-        } else if total_votes == 0 {
-            "NO VOTES".to_string()
+        admit_ballot(
+            proposal,
+            ctx.accounts.prerequisite.as_ref(),
+            ctx.accounts.registry.as_deref(),
+            &BallotSubmission {
+                nullifier,
+                vote: 0,
+                votes,
+                proof_data: &proof_data,
+                voters_root,
+                group,
+                weight,
+                ballot_digest: [0u8; 32],
+                revision: 0,
+            },
+        )?;
+
+        let nullifier_account = &mut ctx.accounts.nullifier_account;
+        nullifier_account.nullifier = nullifier;
+        nullifier_account.proposal = proposal.key();
+        nullifier_account.group = group;
+        nullifier_account.bump = ctx.bumps.nullifier_account;
+
+        let vote_counts = if proposal.voter_groups.is_empty() {
+            &mut proposal.vote_counts
         } else {
-            let label = if !proposal.option_labels[winning_option as usize].is_empty() {
-                proposal.option_labels[winning_option as usize].clone()
-            } else {
-                format!("Option {}", winning_option)
-            };
-            format!("WINNER: {} ({} votes)", label, max_votes)
+            &mut proposal.voter_groups[group as usize].vote_counts
         };
+        for ((count, recorded), &option_votes) in vote_counts
+            .iter_mut()
+            .zip(nullifier_account.counts.iter_mut())
+            .zip(votes.iter())
+        {
+            let weighted_votes = (option_votes as u64)
+                .checked_mul(weight.max(1))
+                .ok_or(VotingError::TallyOverflow)?;
+            *count = count
+                .checked_add(weighted_votes)
+                .ok_or(VotingError::TallyOverflow)?;
+            *recorded = weighted_votes;
+        }
+        proposal.total_votes = proposal
+            .total_votes
+            .checked_add(1)
+            .ok_or(VotingError::TallyOverflow)?;
 
         msg!(
-            "Proposal {} finalized: {} (total: {} votes across {} options)",
+            "Vector vote cast on proposal {}: {:?} (group {}, weight {})",
             proposal.proposal_id,
-            result,
-            total_votes,
-            proposal.num_options
+            &votes[..proposal.num_options as usize],
+            group,
+            weight.max(1)
+        );
+        Ok(())
+    }
+
+    /// Create the buffer an encrypted ballot is uploaded to
+    /// (`BallotMode::Encrypted`; seeds: ["encrypted_ballot", proposal,
+    /// nullifier, payer])
+    ///
+    /// A ballot with its validity proofs doesn't fit in one transaction, so
+    /// it is posted a few options at a time with post_encrypted_options and
+    /// then cast with cast_encrypted_vote, which closes the buffer. Only the
+    /// payer can write to or cast their buffer, and a buffer that is never
+    /// cast can be reclaimed with close_encrypted_ballot after voting ends.
+    pub fn open_encrypted_ballot(
+        ctx: Context<OpenEncryptedBallot>,
+        nullifier: [u8; 32],
+    ) -> Result<()> {
+        let proposal = &ctx.accounts.proposal;
+        let BallotMode::Encrypted { key } = proposal.ballot_mode else {
+            return err!(VotingError::NotEncryptedProposal);
+        };
+        require!(key != [0u8; 64], VotingError::TallyKeyPending);
+        require!(
+            Clock::get()?.unix_timestamp < proposal.voting_ends_at,
+            VotingError::VotingEnded
         );
 
+        let ballot = &mut ctx.accounts.ballot;
+        ballot.ciphertexts = [[0u8; 128]; 8];
+        ballot.posted = 0;
+        ballot.bump = ctx.bumps.ballot;
+
+        msg!(
+            "Encrypted ballot opened on proposal {} (nullifier {:?})",
+            proposal.proposal_id,
+            nullifier
+        );
         Ok(())
     }
-}
 
-// ============================================================================
-// ZK Proof Verification
-// ============================================================================
+    /// Post the ciphertexts of options `first_option..` of an encrypted ballot
+    ///
+    /// Each ciphertext is an exponential-ElGamal encryption (c1 || c2 =
+    /// r*G || m*G + r*key, G1 points) of m = 1 for the chosen option and 0
+    /// otherwise, with a disjunctive Chaum-Pedersen proof that m is 0 or 1.
+    /// The proof's challenge covers the proposal, nullifier and option, so a
+    /// ciphertext can't be copied into another ballot. Options can be posted
+    /// again until the ballot is cast.
+    pub fn post_encrypted_options(
+        ctx: Context<PostEncryptedOptions>,
+        nullifier: [u8; 32],
+        first_option: u8,
+        options: Vec<EncryptedOption>,
+    ) -> Result<()> {
+        let proposal = &ctx.accounts.proposal;
+        let BallotMode::Encrypted { key } = proposal.ballot_mode else {
+            return err!(VotingError::NotEncryptedProposal);
+        };
+        require!(
+            Clock::get()?.unix_timestamp < proposal.voting_ends_at,
+            VotingError::VotingEnded
+        );
+        require!(
+            first_option as usize + options.len() <= proposal.num_options as usize,
+            VotingError::InvalidVote
+        );
 
-/// Verify a Groth16 proof using Solana's altbn254 precompiles
-///
-/// Expected proof format (256 bytes):
-/// - bytes 0-63: proof_a (G1, negated, big-endian)
-/// - bytes 64-191: proof_b (G2, big-endian)
-/// - bytes 192-255: proof_c (G1, big-endian)
-///
-/// When groth16-solana is enabled, this uses ~200k compute units.
-fn verify_groth16_proof(
-    proof_data: &[u8],
-    voters_root: &[u8; 32],
-    nullifier: &[u8; 32],
-    proposal_id: u64,
-    vote: u8,
-    num_options: u8,
-) -> Result<()> {
-    // Validate proof size
-    require!(proof_data.len() >= 256, VotingError::InvalidProof);
+        let proposal_key = proposal.key();
+        let ballot = &mut ctx.accounts.ballot;
+        for (option, entry) in (first_option..).zip(options.iter()) {
+            require!(
+                verify_bit_proof(
+                    &key,
+                    &entry.ciphertext,
+                    &entry.proof,
+                    &[b"bit", proposal_key.as_ref(), &nullifier, &[option]],
+                )?,
+                VotingError::InvalidBallotProof
+            );
+            ballot.ciphertexts[option as usize] = entry.ciphertext;
+            ballot.posted |= 1 << option;
+        }
+
+        msg!(
+            "Encrypted options {}..{} posted on proposal {}",
+            first_option,
+            first_option as usize + options.len(),
+            proposal.proposal_id
+        );
+        Ok(())
+    }
+
+    /// Cast an encrypted ballot once all of its options have been posted
+    ///
+    /// `sum_proof` is a Chaum-Pedersen proof that the product of the
+    /// ballot's ciphertexts encrypts 1, so together with the per-option
+    /// proofs the ballot selects exactly one option. The ZK proof is the same
+    /// membership proof as cast_vote's with `vote` 0 and the ciphertexts'
+    /// digest as its `ballot_digest` input, which ties the ballot to the
+    /// proof. The ciphertexts are added into the proposal's EncryptedTally
+    /// account with the altbn254 addition syscall; nothing about the choice is
+    /// revealed until decrypt_tally.
+    pub fn cast_encrypted_vote(
+        ctx: Context<CastEncryptedVote>,
+        nullifier: [u8; 32],
+        sum_proof: DleqProof,
+        proof_data: Vec<u8>,
+        voters_root: Option<[u8; 32]>,
+    ) -> Result<()> {
+        let proposal = &mut ctx.accounts.proposal;
+        let BallotMode::Encrypted { key } = proposal.ballot_mode else {
+            return err!(VotingError::NotEncryptedProposal);
+        };
+        let num_options = proposal.num_options as usize;
+        let ballot = &ctx.accounts.ballot;
+        require!(
+            ballot.posted as u16 == (1u16 << num_options) - 1,
+            VotingError::EncryptedBallotIncomplete
+        );
+
+        // One-hot: the ciphertexts multiply to an encryption of 1
+        let mut sum = [0u8; 128];
+        for ciphertext in &ballot.ciphertexts[..num_options] {
+            sum = add_ciphertexts(&sum, ciphertext)?;
+        }
+        let (sum_c1, sum_c2) = ciphertext_points(&sum);
+        let sum_c2_minus_one = g1_add(&sum_c2, &g1_neg(&G1_GENERATOR))?;
+        let proposal_key = proposal.key();
+        require!(
+            dleq_holds(
+                &G1_GENERATOR,
+                &sum_c1,
+                &key,
+                &sum_c2_minus_one,
+                &sum_proof,
+                &[b"one-hot", proposal_key.as_ref(), &nullifier],
+            )?,
+            VotingError::InvalidBallotProof
+        );
+
+        admit_ballot(
+            proposal,
+            ctx.accounts.prerequisite.as_ref(),
+            ctx.accounts.registry.as_deref(),
+            &BallotSubmission {
+                nullifier,
+                vote: 0,
+                votes: [0u32; 8],
+                proof_data: &proof_data,
+                voters_root,
+                group: 0,
+                weight: 0,
+                ballot_digest: ballot_digest(&ballot.ciphertexts[..num_options]),
+                revision: 0,
+            },
+        )?;
+
+        let nullifier_account = &mut ctx.accounts.nullifier_account;
+        nullifier_account.nullifier = nullifier;
+        nullifier_account.proposal = proposal_key;
+        nullifier_account.group = 0;
+        nullifier_account.bump = ctx.bumps.nullifier_account;
+
+        // Homomorphic tally: adding ciphertexts adds the encrypted votes
+        for (total, ciphertext) in ctx
+            .accounts
+            .encrypted_tally
+            .ciphertexts
+            .iter_mut()
+            .zip(ballot.ciphertexts[..num_options].iter())
+        {
+            *total = add_ciphertexts(total, ciphertext)?;
+        }
+        proposal.total_votes = proposal
+            .total_votes
+            .checked_add(1)
+            .ok_or(VotingError::TallyOverflow)?;
+
+        msg!(
+            "Encrypted vote cast on proposal {} ({} ballots)",
+            proposal.proposal_id,
+            proposal.total_votes
+        );
+        Ok(())
+    }
+
+    /// Close an encrypted ballot buffer that was never cast, returning its
+    /// rent to the payer
+    ///
+    /// Only the payer can do this, once voting has ended and the ballot can
+    /// no longer be cast.
+    pub fn close_encrypted_ballot(
+        ctx: Context<CloseEncryptedBallot>,
+        nullifier: [u8; 32],
+    ) -> Result<()> {
+        let proposal = &ctx.accounts.proposal;
+        require!(
+            Clock::get()?.unix_timestamp >= proposal.voting_ends_at,
+            VotingError::VotingNotEnded
+        );
+
+        msg!(
+            "Abandoned encrypted ballot closed on proposal {} (nullifier {:?})",
+            proposal.proposal_id,
+            nullifier
+        );
+        Ok(())
+    }
+
+    /// Cast a sealed ballot on a `BallotMode::CommitReveal` or
+    /// `BallotMode::HiddenTally` proposal
+    ///
+    /// `commitment` is Poseidon(vote, salt) for a single-choice `vote` and a
+    /// random `salt` kept by the voter. It is the proof's `ballot_digest`
+    /// input and the circuit checks it opens to a valid option, so nothing
+    /// about the choice is public while voting is open. The commitment is
+    /// stored in a SealedBallot account (seeds: ["sealed_ballot", proposal,
+    /// group, nullifier], matching the nullifier's domain) and counts toward
+    /// vote_counts only once revealed with reveal_vote or reveal_tally.
+    ///
+    /// On hidden-tally proposals `encrypted_opening` should be (vote, salt)
+    /// encrypted to the proposal's tally key; it is kept in the ballot, so
+    /// the sealed ballots form a log the key holder can open after voting
+    /// ends without the voters. Nothing checks that it decrypts to the
+    /// commitment, so a ballot with a bad opening stays sealed unless its
+    /// voter reveals it. It must be empty on commit-reveal proposals.
+    #[allow(clippy::too_many_arguments)]
+    pub fn cast_sealed_vote(
+        ctx: Context<CastSealedVote>,
+        nullifier: [u8; 32],
+        commitment: [u8; 32],
+        proof_data: Vec<u8>,
+        voters_root: Option<[u8; 32]>,
+        group: u8,
+        weight: u64,
+        encrypted_opening: Vec<u8>,
+    ) -> Result<()> {
+        let proposal = &mut ctx.accounts.proposal;
+        match proposal.ballot_mode {
+            BallotMode::CommitReveal { .. } => require!(
+                encrypted_opening.is_empty(),
+                VotingError::InvalidEncryptedOpening
+            ),
+            BallotMode::HiddenTally { .. } => require!(
+                !encrypted_opening.is_empty()
+                    && encrypted_opening.len() <= MAX_ENCRYPTED_OPENING_SIZE,
+                VotingError::InvalidEncryptedOpening
+            ),
+            _ => return err!(VotingError::NotSealedProposal),
+        }
+        require!(commitment != [0u8; 32], VotingError::InvalidCommitment);
+
+        admit_ballot(
+            proposal,
+            ctx.accounts.prerequisite.as_ref(),
+            ctx.accounts.registry.as_deref(),
+            &BallotSubmission {
+                nullifier,
+                vote: 0,
+                votes: [0u32; 8],
+                proof_data: &proof_data,
+                voters_root,
+                group,
+                weight,
+                ballot_digest: commitment,
+                revision: 0,
+            },
+        )?;
+
+        let nullifier_account = &mut ctx.accounts.nullifier_account;
+        nullifier_account.nullifier = nullifier;
+        nullifier_account.proposal = proposal.key();
+        nullifier_account.group = group;
+        nullifier_account.bump = ctx.bumps.nullifier_account;
+
+        let ballot = &mut ctx.accounts.ballot;
+        ballot.proposal = proposal.key();
+        ballot.index = proposal.total_votes;
+        ballot.commitment = commitment;
+        ballot.group = group;
+        ballot.weight = weight.max(1);
+        ballot.encrypted_opening = encrypted_opening;
+        ballot.revealed = false;
+        ballot.bump = ctx.bumps.ballot;
+
+        proposal.total_votes = proposal
+            .total_votes
+            .checked_add(1)
+            .ok_or(VotingError::TallyOverflow)?;
+        proposal.unrevealed_votes = proposal
+            .unrevealed_votes
+            .checked_add(1)
+            .ok_or(VotingError::TallyOverflow)?;
+
+        msg!(
+            "Sealed vote {} cast on proposal {} (group {}, weight {})",
+            ballot.index,
+            proposal.proposal_id,
+            group,
+            ballot.weight
+        );
+        Ok(())
+    }
+
+    /// Open a sealed ballot during the reveal window (permissionless)
+    ///
+    /// The window runs from voting_ends_at to the proposal's
+    /// `reveal_ends_at`. The ballot is found by its nullifier and voter
+    /// `group`. The opening must hash to the stored commitment; the ballot's
+    /// weight is then added to `vote`'s count. Anyone holding the opening can
+    /// reveal, so it can be handed to a relayer; on hidden-tally proposals
+    /// voters can also reveal their own ballot alongside reveal_tally.
+    pub fn reveal_vote(
+        ctx: Context<RevealVote>,
+        nullifier: [u8; 32],
+        group: u8,
+        vote: u8,
+        salt: [u8; 32],
+    ) -> Result<()> {
+        let proposal = &mut ctx.accounts.proposal;
+        proposal.check_reveal_window()?;
+
+        let ballot = &mut ctx.accounts.ballot;
+        require!(!ballot.revealed, VotingError::BallotAlreadyRevealed);
+        proposal.reveal_sealed_ballot(ballot, vote, &salt)?;
+
+        msg!(
+            "Vote revealed on proposal {}: {} (group {}, nullifier {:?}, {} still sealed)",
+            proposal.proposal_id,
+            proposal.option_label(vote),
+            group,
+            nullifier,
+            proposal.unrevealed_votes
+        );
+        Ok(())
+    }
+
+    /// Open a batch of sealed ballots during the reveal window
+    /// (permissionless)
+    ///
+    /// This is how the tally of a hidden-tally proposal is revealed: the
+    /// holder of the tally key decrypts the ballots' `encrypted_opening`s and
+    /// passes the SealedBallot accounts (writable) as remaining accounts,
+    /// with `openings[i]` for the i-th one, as many per transaction as fit.
+    /// Ballots already revealed are skipped, so the crank can be resumed or
+    /// repeated safely. Until then vote_counts stays zero. Ballots whose
+    /// opening can't be recovered stay sealed and are reported as
+    /// unrevealed at finalization.
+    pub fn reveal_tally<'info>(
+        ctx: Context<'_, '_, 'info, 'info, RevealTally<'info>>,
+        openings: Vec<VoteOpening>,
+    ) -> Result<()> {
+        let proposal = &mut ctx.accounts.proposal;
+        proposal.check_reveal_window()?;
+        require!(
+            openings.len() == ctx.remaining_accounts.len(),
+            VotingError::OpeningCountMismatch
+        );
+
+        let proposal_key = proposal.key();
+        let mut revealed = 0u32;
+        for (account, opening) in ctx.remaining_accounts.iter().zip(openings.iter()) {
+            let mut ballot = Account::<SealedBallot>::try_from(account)?;
+            require_keys_eq!(ballot.proposal, proposal_key, VotingError::BallotMismatch);
+            if ballot.revealed {
+                continue;
+            }
+            proposal.reveal_sealed_ballot(&mut ballot, opening.vote, &opening.salt)?;
+            ballot.exit(ctx.program_id)?;
+            revealed += 1;
+        }
+
+        msg!(
+            "{} ballots revealed on proposal {} ({} still sealed)",
+            revealed,
+            proposal.proposal_id,
+            proposal.unrevealed_votes
+        );
+        Ok(())
+    }
+
+    /// Replace a ballot cast with cast_vote or cast_vector_vote on a proposal
+    /// created with `allow_revision`
+    ///
+    /// Takes the same arguments as those instructions: `vote` on single
+    /// choice and approval proposals (with `votes` all zero), `votes` on
+    /// vector proposals (with `vote` 0). The proof must use the same
+    /// nullifier, which only the original voter can derive, so a voter who
+    /// was coerced can change their ballot later without anyone else being
+    /// able to. The previous ballot's counts, kept in the nullifier account,
+    /// are subtracted and the new ones added, so only the latest ballot
+    /// counts. Voting must still be open.
+    ///
+    /// `revision` must be the next one for the nullifier (1 for the first
+    /// revision) and is a public input of the proof, so a proof of an earlier
+    /// revision can't be replayed to bring back the ballot it carried.
+    #[allow(clippy::too_many_arguments)]
+    pub fn revise_vote(
+        ctx: Context<ReviseVote>,
+        nullifier: [u8; 32],
+        vote: u8,
+        votes: [u32; 8],
+        proof_data: Vec<u8>,
+        voters_root: Option<[u8; 32]>,
+        group: u8,
+        weight: u64,
+        revision: u16,
+    ) -> Result<()> {
+        let proposal = &mut ctx.accounts.proposal;
+        let nullifier_account = &mut ctx.accounts.nullifier_account;
+        require!(proposal.allow_revision, VotingError::RevisionNotAllowed);
+        require!(
+            nullifier_account.revisions.checked_add(1) == Some(revision),
+            VotingError::StaleRevision
+        );
+
+        let counts = if proposal.ballot_mode.is_vector() {
+            require!(vote == 0, VotingError::InvalidVote);
+            proposal.validate_vote_vector(&votes)?;
+            let mut counts = [0u64; 8];
+            for (count, &option_votes) in counts.iter_mut().zip(votes.iter()) {
+                *count = (option_votes as u64)
+                    .checked_mul(weight.max(1))
+                    .ok_or(VotingError::TallyOverflow)?;
+            }
+            counts
+        } else {
+            require!(
+                votes.iter().all(|&option_votes| option_votes == 0),
+                VotingError::InvalidVoteVector
+            );
+            let selected = proposal
+                .ballot_mode
+                .selected_options(vote, proposal.num_options)?;
+            let mut counts = [0u64; 8];
+            for (option, count) in counts.iter_mut().enumerate() {
+                if selected & (1 << option) != 0 {
+                    *count = weight.max(1);
+                }
+            }
+            counts
+        };
+
+        admit_ballot(
+            proposal,
+            ctx.accounts.prerequisite.as_ref(),
+            ctx.accounts.registry.as_deref(),
+            &BallotSubmission {
+                nullifier,
+                vote: vote as u32,
+                votes: if proposal.ballot_mode.is_vector() {
+                    votes
+                } else {
+                    [0u32; 8]
+                },
+                proof_data: &proof_data,
+                voters_root,
+                group,
+                weight,
+                ballot_digest: [0u8; 32],
+                revision,
+            },
+        )?;
+
+        let vote_counts = if proposal.voter_groups.is_empty() {
+            &mut proposal.vote_counts
+        } else {
+            &mut proposal.voter_groups[group as usize].vote_counts
+        };
+        for ((count, &old), &new) in vote_counts
+            .iter_mut()
+            .zip(nullifier_account.counts.iter())
+            .zip(counts.iter())
+        {
+            *count = (*count - old)
+                .checked_add(new)
+                .ok_or(VotingError::TallyOverflow)?;
+        }
+        nullifier_account.counts = counts;
+        nullifier_account.revisions = revision;
+
+        msg!(
+            "Vote revised on proposal {}: {:?} (group {}, revision {})",
+            proposal.proposal_id,
+            &counts[..proposal.num_options as usize],
+            group,
+            nullifier_account.revisions
+        );
+        Ok(())
+    }
+
+    /// Run instant-runoff tabulation of a ranked proposal (permissionless)
+    ///
+    /// Pass the proposal's RankedBallot accounts (writable) as remaining
+    /// accounts, as many per transaction as fit. Each ballot counts toward its
+    /// highest-ranked option not yet eliminated; ballots already counted in
+    /// the current round are skipped, so the crank can be resumed or repeated
+    /// safely. Once every ballot has been counted the round closes: an option
+    /// with a majority of the continuing ballots wins, otherwise the last
+    /// placed option is eliminated (ties go against the higher option index)
+    /// and the next round starts.
+    ///
+    /// Every round's counts are kept in `irv.rounds`. When tabulation
+    /// completes, vote_counts holds the final round and finalize_proposal can
+    /// record the outcome; a final round where all continuing options are
+    /// tied finalizes as Tied.
+    pub fn tabulate_irv<'info>(
+        ctx: Context<'_, '_, 'info, 'info, TabulateIrv<'info>>,
+    ) -> Result<()> {
+        let proposal = &mut ctx.accounts.proposal;
+        require!(
+            proposal.ballot_mode == BallotMode::Ranked,
+            VotingError::NotRankedProposal
+        );
+        require!(
+            Clock::get()?.unix_timestamp >= proposal.voting_ends_at,
+            VotingError::VotingNotEnded
+        );
+        require!(!proposal.irv.complete, VotingError::TabulationComplete);
+
+        let proposal_key = proposal.key();
+        let round = proposal.irv.round;
+        for account in ctx.remaining_accounts {
+            let mut ballot = Account::<RankedBallot>::try_from(account)?;
+            require_keys_eq!(
+                ballot.proposal,
+                proposal_key,
+                VotingError::BallotMismatch
+            );
+            if ballot.rounds_counted > round {
+                continue;
+            }
+
+            if let Some(option) = ballot.top_choice(proposal.irv.eliminated) {
+                proposal.irv.current[option] = proposal.irv.current[option]
+                    .checked_add(ballot.weight)
+                    .ok_or(VotingError::TallyOverflow)?;
+            }
+            ballot.rounds_counted = round + 1;
+            ballot.exit(ctx.program_id)?;
+            proposal.irv.ballots_counted += 1;
+        }
+
+        msg!(
+            "IRV round {} of proposal {}: {} of {} ballots counted",
+            round,
+            proposal.proposal_id,
+            proposal.irv.ballots_counted,
+            proposal.total_votes
+        );
+
+        if proposal.irv.ballots_counted == proposal.total_votes {
+            proposal.close_irv_round()?;
+        }
+        Ok(())
+    }
+
+    /// Reveal the totals of an encrypted proposal (permissionless)
+    ///
+    /// Callable once voting has ended. `totals[i]` is the number of votes for
+    /// option i, found off-chain by decrypting the tally with the key's
+    /// secret x and solving the small discrete log. `proofs[i]` is a
+    /// Chaum-Pedersen proof that c2 - totals[i]*G = x*c1 for the key x*G, so
+    /// the totals can be checked without revealing x or any single ballot.
+    /// They are written into vote_counts for finalize_proposal. Proposals
+    /// with a decryption committee are decrypted with
+    /// combine_decryption_shares instead.
+    pub fn decrypt_tally(
+        ctx: Context<DecryptTally>,
+        totals: Vec<u64>,
+        proofs: Vec<DleqProof>,
+    ) -> Result<()> {
+        let proposal = &mut ctx.accounts.proposal;
+        let BallotMode::Encrypted { key } = proposal.ballot_mode else {
+            return err!(VotingError::NotEncryptedProposal);
+        };
+        require!(
+            proposal.committee.is_none(),
+            VotingError::CommitteeDecryptionRequired
+        );
+        require!(
+            Clock::get()?.unix_timestamp >= proposal.voting_ends_at,
+            VotingError::VotingNotEnded
+        );
+        require!(!proposal.is_finalized, VotingError::ProposalFinalized);
+        let encrypted_tally = &mut ctx.accounts.encrypted_tally;
+        require!(
+            !encrypted_tally.decrypted,
+            VotingError::TallyAlreadyDecrypted
+        );
+        let num_options = proposal.num_options as usize;
+        require!(
+            totals.len() == num_options && proofs.len() == num_options,
+            VotingError::InvalidTally
+        );
+
+        let proposal_key = proposal.key();
+        for (option, (&total, proof)) in totals.iter().zip(proofs.iter()).enumerate() {
+            let (c1, c2) = ciphertext_points(&encrypted_tally.ciphertexts[option]);
+            let shared_secret = g1_add(&c2, &g1_neg(&g1_mul(&G1_GENERATOR, &scalar(total))?))?;
+            require!(
+                dleq_holds(
+                    &G1_GENERATOR,
+                    &key,
+                    &c1,
+                    &shared_secret,
+                    proof,
+                    &[b"decrypt", proposal_key.as_ref(), &[option as u8]],
+                )?,
+                VotingError::InvalidDecryptionProof
+            );
+        }
+
+        proposal.vote_counts[..num_options].copy_from_slice(&totals);
+        encrypted_tally.decrypted = true;
+
+        msg!(
+            "Tally of proposal {} decrypted: {:?}",
+            proposal.proposal_id,
+            totals
+        );
+        Ok(())
+    }
+
+    /// Register the threshold committee that decrypts an encrypted proposal
+    /// (seeds: ["committee", proposal])
+    ///
+    /// The proposal must have been created with an all-zero key: the tally
+    /// key is generated by the members themselves (submit_committee_dealing),
+    /// so neither the authority nor any single member holds its secret.
+    /// `members` are the members' wallets, which sign their dealings; member
+    /// j's share of the key is evaluated at j + 1. Any `threshold` members
+    /// can decrypt together, and as long as one member dealt honestly fewer
+    /// than `threshold` learn nothing about the secret. `threshold` colluding
+    /// members can recover it, though, and decrypt single ballots as well as
+    /// the totals. Only the proposal authority can do this, and decrypt_tally
+    /// is disabled on the proposal from then on.
+    ///
+    /// Key generation runs in rounds of `round_duration` seconds each:
+    /// dealings, then complaints, then answers to complaints (see
+    /// file_complaint). Voting can start once fix_committee_key has run.
+    pub fn set_decryption_committee(
+        ctx: Context<SetDecryptionCommittee>,
+        members: Vec<Pubkey>,
+        threshold: u8,
+        round_duration: i64,
+    ) -> Result<()> {
+        let proposal = &mut ctx.accounts.proposal;
+        let BallotMode::Encrypted { key } = proposal.ballot_mode else {
+            return err!(VotingError::NotEncryptedProposal);
+        };
+        require!(key == [0u8; 64], VotingError::TallyKeyAlreadySet);
+        require!(
+            !members.is_empty() && members.len() <= MAX_COMMITTEE_SIZE,
+            VotingError::InvalidCommitteeSize
+        );
+        require!(
+            threshold >= 1 && threshold as usize <= members.len(),
+            VotingError::InvalidThreshold
+        );
+        require!(
+            members
+                .iter()
+                .enumerate()
+                .all(|(index, member)| !members[..index].contains(member)),
+            VotingError::DuplicateCommitteeMember
+        );
+        require!(round_duration > 0, VotingError::InvalidRoundDuration);
+
+        let dealings_end_at = Clock::get()?
+            .unix_timestamp
+            .checked_add(round_duration)
+            .ok_or(VotingError::InvalidRoundDuration)?;
+        let complaints_end_at = dealings_end_at
+            .checked_add(round_duration)
+            .ok_or(VotingError::InvalidRoundDuration)?;
+        let committee = &mut ctx.accounts.committee;
+        committee.proposal = proposal.key();
+        committee.threshold = threshold;
+        committee.share_keys = vec![[0u8; 64]; members.len()];
+        committee.members = members;
+        committee.tally_key = [0u8; 64];
+        committee.dealt = 0;
+        committee.dealing_hashes = [[0u8; 32]; MAX_COMMITTEE_SIZE];
+        committee.complaints = [0u8; MAX_COMMITTEE_SIZE];
+        committee.disqualified = 0;
+        committee.dealings_end_at = dealings_end_at;
+        committee.complaints_end_at = complaints_end_at;
+        committee.answers_end_at = complaints_end_at
+            .checked_add(round_duration)
+            .ok_or(VotingError::InvalidRoundDuration)?;
+        committee.key_fixed = false;
+        committee.shares = [[[0u8; 64]; 8]; MAX_COMMITTEE_SIZE];
+        committee.submitted = [0u8; MAX_COMMITTEE_SIZE];
+        committee.bump = ctx.bumps.committee;
+        proposal.committee = Some(committee.key());
+
+        msg!(
+            "Decryption committee of proposal {}: {} of {} members",
+            proposal.proposal_id,
+            threshold,
+            committee.members.len()
+        );
+        Ok(())
+    }
+
+    /// Submit a committee member's dealing of the distributed key generation
+    /// (signed by the member's wallet)
+    ///
+    /// The member picks a random polynomial f_i of degree threshold - 1 and
+    /// publishes `commitments[t]` = a_t*G for each coefficient (Feldman),
+    /// with `proof` a Schnorr proof of knowledge of a_0 so a member can't
+    /// cancel out the others' keys. f_i(j + 1) is sent privately to member j,
+    /// who checks it against the commitments off-chain and files a complaint
+    /// if it doesn't match. Each dealing adds sum((j + 1)^t * commitments[t])
+    /// to member j's share key and a_0*G to the tally key. Dealings are
+    /// accepted until the dealing round ends; members that don't deal still
+    /// hold a share. Large committees need extra compute units.
+    pub fn submit_committee_dealing(
+        ctx: Context<SubmitCommitteeDealing>,
+        member: u8,
+        commitments: Vec<[u8; 64]>,
+        proof: DleqProof,
+    ) -> Result<()> {
+        let proposal = &ctx.accounts.proposal;
+        let committee = &mut ctx.accounts.committee;
+        require!(
+            committee.members.get(member as usize) == Some(&ctx.accounts.member.key()),
+            VotingError::InvalidCommitteeMember
+        );
+        require!(
+            Clock::get()?.unix_timestamp < committee.dealings_end_at,
+            VotingError::DealingRoundEnded
+        );
+        require!(
+            committee.dealt & (1 << member) == 0,
+            VotingError::DealingAlreadySubmitted
+        );
+        require!(
+            commitments.len() == committee.threshold as usize,
+            VotingError::InvalidDealing
+        );
+        let proposal_key = proposal.key();
+        require!(
+            dleq_holds(
+                &G1_GENERATOR,
+                &commitments[0],
+                &G1_GENERATOR,
+                &commitments[0],
+                &proof,
+                &[b"dealing", proposal_key.as_ref(), &[member]],
+            )?,
+            VotingError::InvalidDealing
+        );
+
+        for (index, share_key) in committee.share_keys.iter_mut().enumerate() {
+            let evaluation = feldman_evaluation(&commitments, index as u8)?;
+            *share_key = g1_add(share_key, &evaluation)?;
+        }
+        committee.tally_key = g1_add(&committee.tally_key, &commitments[0])?;
+        committee.dealing_hashes[member as usize] = dealing_hash(&commitments);
+        committee.dealt |= 1 << member;
+
+        msg!(
+            "Member {} dealt on proposal {} ({} of {} dealings): {:?}",
+            member,
+            proposal.proposal_id,
+            committee.dealt.count_ones(),
+            committee.members.len(),
+            commitments
+        );
+        Ok(())
+    }
+
+    /// Complain that a dealer's private share doesn't match its commitments
+    /// (signed by the complaining member's wallet)
+    ///
+    /// Filed by member `member` against dealer `dealer` before the complaint
+    /// round ends. The dealer must answer it with answer_complaint before the
+    /// answer round ends, or be disqualified with disqualify_dealer.
+    pub fn file_complaint(ctx: Context<FileComplaint>, member: u8, dealer: u8) -> Result<()> {
+        let committee = &mut ctx.accounts.committee;
+        require!(
+            committee.members.get(member as usize) == Some(&ctx.accounts.member.key()),
+            VotingError::InvalidCommitteeMember
+        );
+        require!(
+            Clock::get()?.unix_timestamp < committee.complaints_end_at,
+            VotingError::ComplaintRoundEnded
+        );
+        require!(
+            (dealer as usize) < committee.members.len() && committee.dealt & (1 << dealer) != 0,
+            VotingError::InvalidCommitteeMember
+        );
+        require!(
+            committee.disqualified & (1 << dealer) == 0,
+            VotingError::DealerDisqualified
+        );
+        committee.complaints[dealer as usize] |= 1 << member;
+
+        msg!(
+            "Member {} complained about dealer {} on proposal {}",
+            member,
+            dealer,
+            ctx.accounts.proposal.proposal_id
+        );
+        Ok(())
+    }
+
+    /// Answer a complaint by publishing the disputed share (signed by the
+    /// dealer's wallet)
+    ///
+    /// `share` is f_dealer(member + 1), checked against the dealer's
+    /// `commitments` (as submitted in its dealing), and replaces the share
+    /// the complaining member was sent privately. Callable until the answer
+    /// round ends.
+    pub fn answer_complaint(
+        ctx: Context<AnswerComplaint>,
+        dealer: u8,
+        member: u8,
+        share: [u8; 32],
+        commitments: Vec<[u8; 64]>,
+    ) -> Result<()> {
+        let committee = &mut ctx.accounts.committee;
+        require!(
+            committee.members.get(dealer as usize) == Some(&ctx.accounts.dealer.key()),
+            VotingError::InvalidCommitteeMember
+        );
+        require!(
+            Clock::get()?.unix_timestamp < committee.answers_end_at,
+            VotingError::ComplaintRoundEnded
+        );
+        require!(
+            (member as usize) < committee.members.len()
+                && committee.complaints[dealer as usize] & (1 << member) != 0,
+            VotingError::NoComplaint
+        );
+        require!(
+            dealing_hash(&commitments) == committee.dealing_hashes[dealer as usize]
+                && is_scalar(&share)
+                && g1_mul(&G1_GENERATOR, &share)? == feldman_evaluation(&commitments, member)?,
+            VotingError::InvalidComplaintAnswer
+        );
+        committee.complaints[dealer as usize] &= !(1 << member);
+
+        msg!(
+            "Dealer {} answered member {} on proposal {}: {:?}",
+            dealer,
+            member,
+            ctx.accounts.proposal.proposal_id,
+            share
+        );
+        Ok(())
+    }
+
+    /// Disqualify a dealer that left a complaint unanswered (permissionless)
+    ///
+    /// Callable once the answer round has ended. The dealer's `commitments`
+    /// (as submitted in its dealing) are taken back out of the share keys and
+    /// the tally key; the dealer stays a member and decrypts with the share
+    /// of the remaining dealings.
+    pub fn disqualify_dealer(
+        ctx: Context<DisqualifyDealer>,
+        dealer: u8,
+        commitments: Vec<[u8; 64]>,
+    ) -> Result<()> {
+        let committee = &mut ctx.accounts.committee;
+        require!(
+            Clock::get()?.unix_timestamp >= committee.answers_end_at,
+            VotingError::ComplaintRoundOpen
+        );
+        require!(
+            (dealer as usize) < committee.members.len()
+                && committee.complaints[dealer as usize] != 0,
+            VotingError::NoComplaint
+        );
+        require!(
+            dealing_hash(&commitments) == committee.dealing_hashes[dealer as usize],
+            VotingError::InvalidDealing
+        );
+
+        for (index, share_key) in committee.share_keys.iter_mut().enumerate() {
+            let evaluation = feldman_evaluation(&commitments, index as u8)?;
+            *share_key = g1_add(share_key, &g1_neg(&evaluation))?;
+        }
+        committee.tally_key = g1_add(&committee.tally_key, &g1_neg(&commitments[0]))?;
+        committee.complaints[dealer as usize] = 0;
+        committee.disqualified |= 1 << dealer;
+
+        msg!(
+            "Dealer {} disqualified on proposal {}",
+            dealer,
+            ctx.accounts.proposal.proposal_id
+        );
+        Ok(())
+    }
+
+    /// Fix the committee's tally key and open voting (permissionless)
+    ///
+    /// Callable once the complaint round has ended and every complaint has
+    /// been answered or its dealer disqualified. The tally key is the sum of
+    /// the remaining dealings' a_0*G, of which there must be at least one.
+    pub fn fix_committee_key(ctx: Context<FixCommitteeKey>) -> Result<()> {
+        let proposal = &mut ctx.accounts.proposal;
+        let committee = &mut ctx.accounts.committee;
+        require!(!committee.key_fixed, VotingError::TallyKeyAlreadySet);
+        require!(
+            Clock::get()?.unix_timestamp >= committee.complaints_end_at,
+            VotingError::ComplaintRoundOpen
+        );
+        require!(
+            committee.complaints.iter().all(|&complaints| complaints == 0),
+            VotingError::ComplaintsOutstanding
+        );
+        require!(
+            committee.dealt & !committee.disqualified != 0,
+            VotingError::NoQualifiedDealings
+        );
+
+        proposal.ballot_mode = BallotMode::Encrypted {
+            key: committee.tally_key,
+        };
+        committee.key_fixed = true;
+
+        msg!(
+            "Tally key of proposal {} from dealers {:#010b}: {:?}",
+            proposal.proposal_id,
+            committee.dealt & !committee.disqualified,
+            committee.tally_key
+        );
+        Ok(())
+    }
+
+    /// Submit a committee member's partial decryptions of options
+    /// `first_option..` of the tally (permissionless)
+    ///
+    /// Callable once voting has ended. Each share is x_j*c1 for the option's
+    /// tally ciphertext, with a Chaum-Pedersen proof that it uses the same
+    /// secret as the member's key x_j*G, so a share can only come from the
+    /// member. Large proposals submit their options over several
+    /// transactions.
+    pub fn submit_decryption_share(
+        ctx: Context<SubmitDecryptionShare>,
+        member: u8,
+        first_option: u8,
+        shares: Vec<PartialDecryption>,
+    ) -> Result<()> {
+        let proposal = &ctx.accounts.proposal;
+        require!(
+            Clock::get()?.unix_timestamp >= proposal.voting_ends_at,
+            VotingError::VotingNotEnded
+        );
+        require!(!proposal.is_finalized, VotingError::ProposalFinalized);
+        let encrypted_tally = &ctx.accounts.encrypted_tally;
+        require!(
+            !encrypted_tally.decrypted,
+            VotingError::TallyAlreadyDecrypted
+        );
+        require!(
+            first_option as usize + shares.len() <= proposal.num_options as usize,
+            VotingError::InvalidTally
+        );
+
+        let proposal_key = proposal.key();
+        let committee = &mut ctx.accounts.committee;
+        require!(committee.key_fixed, VotingError::TallyKeyPending);
+        let member_key = *committee
+            .share_keys
+            .get(member as usize)
+            .ok_or(VotingError::InvalidCommitteeMember)?;
+        for (option, partial) in (first_option..).zip(shares.iter()) {
+            let (c1, _) = ciphertext_points(&encrypted_tally.ciphertexts[option as usize]);
+            require!(
+                dleq_holds(
+                    &G1_GENERATOR,
+                    &member_key,
+                    &c1,
+                    &partial.share,
+                    &partial.proof,
+                    &[b"share", proposal_key.as_ref(), &[member], &[option]],
+                )?,
+                VotingError::InvalidDecryptionProof
+            );
+            committee.shares[member as usize][option as usize] = partial.share;
+            committee.submitted[member as usize] |= 1 << option;
+        }
+
+        msg!(
+            "Member {} submitted decryption shares {}..{} of proposal {}",
+            member,
+            first_option,
+            first_option as usize + shares.len(),
+            proposal.proposal_id
+        );
+        Ok(())
+    }
+
+    /// Combine the committee's shares and reveal the totals (permissionless)
+    ///
+    /// Uses the first `threshold` members that have submitted a share for
+    /// every option. Their shares combine by Lagrange interpolation into
+    /// x*c1, so c2 - x*c1 = totals[i]*G; `totals` are found off-chain from
+    /// the same shares and checked here. The coefficients are scaled by a
+    /// common denominator to keep them integers. The totals are written into
+    /// vote_counts for finalize_proposal.
+    pub fn combine_decryption_shares(
+        ctx: Context<CombineDecryptionShares>,
+        totals: Vec<u64>,
+    ) -> Result<()> {
+        let proposal = &mut ctx.accounts.proposal;
+        let committee = &ctx.accounts.committee;
+        let encrypted_tally = &mut ctx.accounts.encrypted_tally;
+        require!(!proposal.is_finalized, VotingError::ProposalFinalized);
+        require!(
+            !encrypted_tally.decrypted,
+            VotingError::TallyAlreadyDecrypted
+        );
+        let num_options = proposal.num_options as usize;
+        require!(totals.len() == num_options, VotingError::InvalidTally);
+
+        let complete = ((1u16 << num_options) - 1) as u8;
+        let signers: Vec<u8> = (0..committee.members.len() as u8)
+            .filter(|&member| committee.submitted[member as usize] == complete)
+            .take(committee.threshold as usize)
+            .collect();
+        require!(
+            signers.len() == committee.threshold as usize,
+            VotingError::NotEnoughShares
+        );
+        let (denominator, coefficients) = lagrange_coefficients(&signers);
+
+        for (option, &total) in totals.iter().enumerate() {
+            let (_, c2) = ciphertext_points(&encrypted_tally.ciphertexts[option]);
+            let message = g1_add(&c2, &g1_neg(&g1_mul(&G1_GENERATOR, &scalar(total))?))?;
+            let expected = g1_mul(&message, &scalar(denominator))?;
+
+            let mut combined = [0u8; 64];
+            for (&member, &(coefficient, negative)) in signers.iter().zip(coefficients.iter()) {
+                let term = g1_mul(
+                    &committee.shares[member as usize][option],
+                    &scalar(coefficient),
+                )?;
+                let term = if negative { g1_neg(&term) } else { term };
+                combined = g1_add(&combined, &term)?;
+            }
+            require!(combined == expected, VotingError::InvalidTally);
+        }
+
+        proposal.vote_counts[..num_options].copy_from_slice(&totals);
+        encrypted_tally.decrypted = true;
+
+        msg!(
+            "Tally of proposal {} decrypted by members {:?}: {:?}",
+            proposal.proposal_id,
+            signers,
+            totals
+        );
+        Ok(())
+    }
+
+    /// Finalize voting and lock results (multi-choice)
+    pub fn finalize_proposal(ctx: Context<FinalizeProposal>) -> Result<()> {
+        let proposal = &mut ctx.accounts.proposal;
+
+        let clock = Clock::get()?;
+        require!(
+            clock.unix_timestamp >= proposal.voting_ends_at,
+            VotingError::VotingNotEnded
+        );
+        require!(!proposal.is_finalized, VotingError::ProposalFinalized);
+        require!(
+            proposal.ballot_mode != BallotMode::Ranked || proposal.irv.complete,
+            VotingError::TabulationIncomplete
+        );
+        if proposal.ballot_mode.is_encrypted() {
+            let encrypted_tally = ctx
+                .accounts
+                .encrypted_tally
+                .as_ref()
+                .ok_or(VotingError::EncryptedTallyMismatch)?;
+            require!(encrypted_tally.decrypted, VotingError::TallyNotDecrypted);
+        }
+        if let Some(reveal_ends_at) = proposal.ballot_mode.reveal_ends_at() {
+            require!(
+                clock.unix_timestamp >= reveal_ends_at,
+                VotingError::RevealWindowOpen
+            );
+        }
+
+        proposal.is_finalized = true;
+
+        // Multi-group proposals decide on the weighted sum of their group tallies
+        if !proposal.voter_groups.is_empty() {
+            proposal.combine_group_tallies()?;
+        }
+
+        // Find winning option(s) for multi-choice voting
+        let mut max_votes = 0u64;
+        let mut winning_option: u8 = 0;
+        let mut total_votes = 0u64;
+
+        for i in 0..proposal.num_options as usize {
+            total_votes = total_votes
+                .checked_add(proposal.vote_counts[i])
+                .ok_or(VotingError::TallyOverflow)?;
+            if proposal.vote_counts[i] > max_votes {
+                max_votes = proposal.vote_counts[i];
+                winning_option = i as u8;
+            }
+        }
+
+        // Check for ties
+        let mut tie_count = 0;
+        for i in 0..proposal.num_options as usize {
+            if proposal.vote_counts[i] == max_votes {
+                tie_count += 1;
+            }
+        }
+
+        // Store the outcome so treasury actions can be gated on it
+        proposal.outcome = if total_votes == 0 {
+            ProposalOutcome::NoVotes
+        } else if tie_count > 1 {
+            ProposalOutcome::Tied
+        } else {
+            ProposalOutcome::Succeeded
+        };
+        proposal.winning_option = winning_option;
+
+        // Turnout in basis points of the eligible voters recorded at creation
+        // (ballots, not weighted votes). Sealed ballots never revealed had no
+        // say in the outcome, so they are left out
+        let counted_ballots = proposal.total_votes - proposal.unrevealed_votes;
+        proposal.turnout_bps = if proposal.eligible_voters == 0 {
+            0
+        } else {
+            (counted_ballots as u128 * 10_000 / proposal.eligible_voters as u128).min(10_000) as u16
+        };
+
+        let result = match proposal.outcome {
+            ProposalOutcome::NoVotes => "NO VOTES".to_string(),
+            ProposalOutcome::Tied => "TIE".to_string(),
+            _ => format!(
+                "WINNER: {} ({} votes)",
+                proposal.option_label(winning_option),
+                max_votes
+            ),
+        };
+
+        msg!(
+            "Proposal {} finalized: {} (total: {} votes across {} options)",
+            proposal.proposal_id,
+            result,
+            total_votes,
+            proposal.num_options
+        );
+        msg!(
+            "Turnout: {} of {} eligible voters ({} bps)",
+            counted_ballots,
+            proposal.eligible_voters,
+            proposal.turnout_bps
+        );
+        if proposal.ballot_mode.reveal_ends_at().is_some() {
+            msg!(
+                "{} of {} sealed ballots were never revealed",
+                proposal.unrevealed_votes,
+                proposal.total_votes
+            );
+        }
+
+        Ok(())
+    }
+
+    /// Execute a treasury action attached to the winning option
+    ///
+    /// Permissionless: anyone can crank the actions of a passed proposal. The
+    /// accounts the action touches are passed as remaining accounts and must
+    /// match the keys stored when the action was attached. The proposal's
+    /// turnout must also have reached the DAO's quorum.
+    ///
+    /// `UpgradeProgram` additionally needs the program data account, the rent
+    /// and clock sysvars and the upgradeable loader among the remaining accounts.
+    ///
+    /// Disabled while proof verification is off, upgrades included: ballots
+    /// are unchecked then, so a passed proposal says nothing about what
+    /// eligible voters decided.
+    pub fn execute_action<'info>(
+        ctx: Context<'_, '_, 'info, 'info, ExecuteAction<'info>>,
+    ) -> Result<()> {
+        let proposal = &ctx.accounts.proposal;
+        let action = &mut ctx.accounts.action;
+
+        require!(proposal.is_finalized, VotingError::ProposalNotFinalized);
+        require!(
+            proposal.outcome == ProposalOutcome::Succeeded
+                && proposal.winning_option == action.option,
+            VotingError::ActionNotApproved
+        );
+        require!(!action.executed, VotingError::ActionAlreadyExecuted);
+        require!(
+            proposal.turnout_bps >= ctx.accounts.dao.quorum_bps,
+            VotingError::QuorumNotMet
+        );
+        require!(
+            verifying_key::VERIFICATION_ENABLED,
+            VotingError::VerificationDisabled
+        );
+        check_prerequisite(proposal, ctx.accounts.prerequisite.as_ref())?;
+
+        let dao_key = ctx.accounts.dao.key();
+        let treasury_seeds: &[&[&[u8]]] = &[&[
+            b"treasury",
+            dao_key.as_ref(),
+            &[ctx.accounts.dao.treasury_bump],
+        ]];
+        let treasury = ctx.accounts.treasury.to_account_info();
+
+        match action.action {
+            Action::TransferSol {
+                recipient,
+                lamports,
+            } => {
+                let recipient = action_account(ctx.remaining_accounts, &recipient)?;
+                system_program::transfer(
+                    CpiContext::new_with_signer(
+                        ctx.accounts.system_program.to_account_info(),
+                        system_program::Transfer {
+                            from: treasury,
+                            to: recipient.clone(),
+                        },
+                        treasury_seeds,
+                    ),
+                    lamports,
+                )?;
+            }
+            Action::TransferToken {
+                source,
+                destination,
+                amount,
+            } => {
+                let source = action_account(ctx.remaining_accounts, &source)?;
+                let destination = action_account(ctx.remaining_accounts, &destination)?;
+                token::transfer(
+                    CpiContext::new_with_signer(
+                        ctx.accounts.token_program.to_account_info(),
+                        token::Transfer {
+                            from: source.clone(),
+                            to: destination.clone(),
+                            authority: treasury,
+                        },
+                        treasury_seeds,
+                    ),
+                    amount,
+                )?;
+            }
+            Action::CloseAccount {
+                account,
+                destination,
+            } => {
+                let account = action_account(ctx.remaining_accounts, &account)?;
+                let destination = action_account(ctx.remaining_accounts, &destination)?;
+                token::close_account(CpiContext::new_with_signer(
+                    ctx.accounts.token_program.to_account_info(),
+                    token::CloseAccount {
+                        account: account.clone(),
+                        destination: destination.clone(),
+                        authority: treasury,
+                    },
+                    treasury_seeds,
+                ))?;
+            }
+            Action::UpgradeProgram {
+                program,
+                buffer,
+                spill,
+            } => {
+                let program_data = bpf_loader_upgradeable::get_program_data_address(&program);
+                let upgrade_ix =
+                    bpf_loader_upgradeable::upgrade(&program, &buffer, &dao_key, &spill);
+                let accounts = [
+                    action_account(ctx.remaining_accounts, &program_data)?.clone(),
+                    action_account(ctx.remaining_accounts, &program)?.clone(),
+                    action_account(ctx.remaining_accounts, &buffer)?.clone(),
+                    action_account(ctx.remaining_accounts, &spill)?.clone(),
+                    action_account(ctx.remaining_accounts, &Rent::id())?.clone(),
+                    action_account(ctx.remaining_accounts, &Clock::id())?.clone(),
+                    ctx.accounts.dao.to_account_info(),
+                    action_account(ctx.remaining_accounts, &bpf_loader_upgradeable::ID)?.clone(),
+                ];
+
+                // The DAO PDA itself is the program's upgrade authority
+                let dao_id_bytes = ctx.accounts.dao.dao_id.to_le_bytes();
+                let dao_seeds: &[&[&[u8]]] =
+                    &[&[b"dao", dao_id_bytes.as_ref(), &[ctx.accounts.dao.bump]]];
+                invoke_signed(&upgrade_ix, &accounts, dao_seeds)?;
+            }
+        }
+
+        action.executed = true;
+
+        msg!(
+            "Action {} of proposal {} executed from DAO {} treasury",
+            action.index,
+            proposal.proposal_id,
+            ctx.accounts.dao.dao_id
+        );
+        Ok(())
+    }
+
+    /// Nominate a new proposal authority (step 1 of 2)
+    ///
+    /// The current authority stays in control until the nominee calls
+    /// `accept_authority_transfer`. Nominating again replaces the pending key.
+    pub fn propose_authority_transfer(
+        ctx: Context<ProposeAuthorityTransfer>,
+        new_authority: Pubkey,
+    ) -> Result<()> {
+        let proposal = &mut ctx.accounts.proposal;
+        propose_transfer(
+            proposal.key(),
+            proposal.authority,
+            &mut proposal.pending_authority,
+            new_authority,
+        );
+        Ok(())
+    }
+
+    /// Accept a pending proposal authority transfer (step 2 of 2)
+    pub fn accept_authority_transfer(ctx: Context<AcceptAuthorityTransfer>) -> Result<()> {
+        let account = ctx.accounts.proposal.key();
+        let proposal: &mut Proposal = &mut ctx.accounts.proposal;
+        accept_transfer(
+            account,
+            &mut proposal.authority,
+            &mut proposal.pending_authority,
+        )
+    }
+
+    /// Nominate a new DAO authority (step 1 of 2)
+    pub fn propose_dao_authority_transfer(
+        ctx: Context<ProposeDaoAuthorityTransfer>,
+        new_authority: Pubkey,
+    ) -> Result<()> {
+        let dao = &mut ctx.accounts.dao;
+        propose_transfer(
+            dao.key(),
+            dao.authority,
+            &mut dao.pending_authority,
+            new_authority,
+        );
+        Ok(())
+    }
+
+    /// Accept a pending DAO authority transfer (step 2 of 2)
+    pub fn accept_dao_authority_transfer(ctx: Context<AcceptDaoAuthorityTransfer>) -> Result<()> {
+        let account = ctx.accounts.dao.key();
+        let dao: &mut Dao = &mut ctx.accounts.dao;
+        accept_transfer(account, &mut dao.authority, &mut dao.pending_authority)
+    }
+
+    /// Nominate a new registry update authority (step 1 of 2)
+    pub fn propose_registry_authority_transfer(
+        ctx: Context<ProposeRegistryAuthorityTransfer>,
+        new_authority: Pubkey,
+    ) -> Result<()> {
+        let registry = &mut ctx.accounts.registry;
+        propose_transfer(
+            registry.key(),
+            registry.authority,
+            &mut registry.pending_authority,
+            new_authority,
+        );
+        Ok(())
+    }
+
+    /// Accept a pending registry authority transfer (step 2 of 2)
+    pub fn accept_registry_authority_transfer(
+        ctx: Context<AcceptRegistryAuthorityTransfer>,
+    ) -> Result<()> {
+        let account = ctx.accounts.registry.key();
+        let registry: &mut VoterRegistry = &mut ctx.accounts.registry;
+        accept_transfer(
+            account,
+            &mut registry.authority,
+            &mut registry.pending_authority,
+        )
+    }
+}
+
+// ============================================================================
+// Voter Groups
+// ============================================================================
+
+impl Proposal {
+    /// Whether ballots carry the leaf's weight as a public input; quadratic
+    /// proposals keep it private as the voter's credit budget
+    fn reveals_weight(&self) -> bool {
+        self.weighted && self.ballot_mode != BallotMode::Quadratic
+    }
+
+    /// Check a vector ballot: entries from num_options on are zero, at least
+    /// one vote is cast, and points ballots spend exactly the point budget
+    fn validate_vote_vector(&self, votes: &[u32; 8]) -> Result<()> {
+        require!(
+            votes[self.num_options as usize..]
+                .iter()
+                .all(|&option_votes| option_votes == 0)
+                && votes.iter().any(|&option_votes| option_votes > 0),
+            VotingError::InvalidVoteVector
+        );
+        if let BallotMode::Points { budget } = self.ballot_mode {
+            require!(
+                votes.iter().map(|&points| points as u64).sum::<u64>() == budget as u64,
+                VotingError::PointBudgetMismatch
+            );
+        }
+        Ok(())
+    }
+
+    /// Label of an option, or "Option N" when it has none
+    fn option_label(&self, option: u8) -> String {
+        let label = &self.option_labels[option as usize];
+        if !label.is_empty() {
+            label.clone()
+        } else {
+            format!("Option {}", option)
+        }
+    }
+
+    /// Ensure the reveal window of a sealed-ballot proposal is open
+    fn check_reveal_window(&self) -> Result<()> {
+        let reveal_ends_at = self
+            .ballot_mode
+            .reveal_ends_at()
+            .ok_or(VotingError::NotSealedProposal)?;
+        let now = Clock::get()?.unix_timestamp;
+        require!(now >= self.voting_ends_at, VotingError::VotingNotEnded);
+        require!(now < reveal_ends_at, VotingError::RevealWindowClosed);
+        Ok(())
+    }
+
+    /// Check an opening against a sealed ballot's commitment and count it
+    fn reveal_sealed_ballot(
+        &mut self,
+        ballot: &mut SealedBallot,
+        vote: u8,
+        salt: &[u8; 32],
+    ) -> Result<()> {
+        require!(vote < self.num_options, VotingError::InvalidVote);
+        require!(
+            poseidon_hash(&scalar(vote as u64), salt)? == ballot.commitment,
+            VotingError::RevealMismatch
+        );
+
+        let vote_counts = if self.voter_groups.is_empty() {
+            &mut self.vote_counts
+        } else {
+            &mut self.voter_groups[ballot.group as usize].vote_counts
+        };
+        vote_counts[vote as usize] = vote_counts[vote as usize]
+            .checked_add(ballot.weight)
+            .ok_or(VotingError::TallyOverflow)?;
+        ballot.revealed = true;
+        self.unrevealed_votes -= 1;
+        Ok(())
+    }
+
+    /// Whether any ballot has been cast, in any voter group; encrypted
+    /// ballots only show up in total_votes until the tally is decrypted
+    fn has_votes(&self) -> bool {
+        self.total_votes > 0
+    }
+
+    /// Write the weighted sum of the group tallies into vote_counts
+    fn combine_group_tallies(&mut self) -> Result<()> {
+        let mut combined = [0u64; 8];
+        for group in &self.voter_groups {
+            for (total, &count) in combined.iter_mut().zip(group.vote_counts.iter()) {
+                let weighted = count
+                    .checked_mul(group.weight as u64)
+                    .ok_or(VotingError::TallyOverflow)?;
+                *total = total
+                    .checked_add(weighted)
+                    .ok_or(VotingError::TallyOverflow)?;
+            }
+        }
+        self.vote_counts = combined;
+        Ok(())
+    }
+}
+
+// ============================================================================
+// Ranked-Choice Tabulation
+// ============================================================================
+
+/// Marks the end of a packed ranking
+const RANKING_END: u32 = 0xF;
+
+/// Pack a ranking into a u32, one option per nibble from the lowest, with
+/// RANKING_END after the last ranked option (same encoding the circuit checks)
+fn pack_ranking(ranking: &[u8], num_options: u8) -> Result<u32> {
+    require!(
+        !ranking.is_empty() && ranking.len() <= num_options as usize,
+        VotingError::InvalidRanking
+    );
+    let mut seen = 0u8;
+    let mut packed = u32::MAX;
+    for (rank, &option) in ranking.iter().enumerate() {
+        require!(
+            option < num_options && seen & (1 << option) == 0,
+            VotingError::InvalidRanking
+        );
+        seen |= 1 << option;
+        packed &= !(RANKING_END << (4 * rank));
+        packed |= (option as u32) << (4 * rank);
+    }
+    Ok(packed)
+}
+
+impl RankedBallot {
+    /// Highest-ranked option that has not been eliminated, if any
+    fn top_choice(&self, eliminated: u8) -> Option<usize> {
+        (0..MAX_VOTE_OPTIONS as usize)
+            .map(|rank| (self.ranking >> (4 * rank)) & RANKING_END)
+            .take_while(|&option| option != RANKING_END)
+            .map(|option| option as usize)
+            .find(|&option| eliminated & (1 << option) == 0)
+    }
+}
+
+impl Proposal {
+    /// Close the current IRV round once every ballot has been counted
+    ///
+    /// Completes the tabulation on a majority of the continuing ballots or
+    /// when all continuing options are tied; otherwise eliminates the last
+    /// placed option and resets the counts for the next round.
+    fn close_irv_round(&mut self) -> Result<()> {
+        let counts = self.irv.current;
+        self.irv.rounds.push(counts);
+
+        let continuing: Vec<usize> = (0..self.num_options as usize)
+            .filter(|&option| self.irv.eliminated & (1 << option) == 0)
+            .collect();
+        let mut active = 0u64;
+        for &option in &continuing {
+            active = active
+                .checked_add(counts[option])
+                .ok_or(VotingError::TallyOverflow)?;
+        }
+        let max = continuing.iter().map(|&option| counts[option]).max().unwrap_or(0);
+        let min = continuing.iter().map(|&option| counts[option]).min().unwrap_or(0);
+
+        if max as u128 * 2 > active as u128 || max == min {
+            self.irv.complete = true;
+            self.vote_counts = counts;
+            msg!(
+                "IRV tabulation of proposal {} complete after {} rounds",
+                self.proposal_id,
+                self.irv.rounds.len()
+            );
+            return Ok(());
+        }
+
+        // Eliminate the last-placed option; ties go against the higher index
+        let loser = *continuing
+            .iter()
+            .rev()
+            .find(|&&option| counts[option] == min)
+            .ok_or(VotingError::TallyOverflow)?;
+        self.irv.eliminated |= 1 << loser;
+        self.irv.round += 1;
+        self.irv.current = [0u64; 8];
+        self.irv.ballots_counted = 0;
+
+        msg!(
+            "IRV round {} of proposal {}: {} eliminated with {} votes",
+            self.irv.round - 1,
+            self.proposal_id,
+            self.option_label(loser as u8),
+            min
+        );
+        Ok(())
+    }
+}
+
+// ============================================================================
+// Authority Transfer
+// ============================================================================
+
+/// Record `new_authority` as the pending authority of `account`
+fn propose_transfer(
+    account: Pubkey,
+    authority: Pubkey,
+    pending_authority: &mut Option<Pubkey>,
+    new_authority: Pubkey,
+) {
+    *pending_authority = Some(new_authority);
+
+    emit!(AuthorityTransferProposed {
+        account,
+        authority,
+        pending_authority: new_authority,
+    });
+    msg!("Authority transfer of {} proposed to {}", account, new_authority);
+}
+
+/// Promote the pending authority of `account`; account constraints have
+/// already checked that the pending authority signed
+fn accept_transfer(
+    account: Pubkey,
+    authority: &mut Pubkey,
+    pending_authority: &mut Option<Pubkey>,
+) -> Result<()> {
+    let new_authority = pending_authority
+        .take()
+        .ok_or(VotingError::NotPendingAuthority)?;
+    let previous_authority = std::mem::replace(authority, new_authority);
+
+    emit!(AuthorityTransferAccepted {
+        account,
+        previous_authority,
+        new_authority,
+    });
+    msg!("Authority of {} transferred to {}", account, new_authority);
+    Ok(())
+}
+
+// ============================================================================
+// Voter Registry Tree
+// ============================================================================
+
+impl VoterRegistry {
+    /// Append a leaf to the incremental Merkle tree and update the root
+    ///
+    /// Only the rightmost filled node of each level is stored: a left child is
+    /// paired with the empty subtree at that level, a right child with the
+    /// stored left sibling. The new root is pushed onto the root history.
+    /// Returns the leaf index.
+    fn insert(&mut self, leaf: [u8; 32], slot: u64) -> Result<u64> {
+        require!(leaf != [0u8; 32], VotingError::InvalidCommitment);
+        let index = self.next_index;
+        require!(index < 1u64 << TREE_DEPTH, VotingError::RegistryFull);
+
+        let mut current_index = index;
+        let mut current = leaf;
+        for (filled, zero) in self.filled_subtrees.iter_mut().zip(ZERO_HASHES.iter()) {
+            let (left, right) = if current_index & 1 == 0 {
+                *filled = current;
+                (current, *zero)
+            } else {
+                (*filled, current)
+            };
+            current = poseidon_hash(&left, &right)?;
+            current_index /= 2;
+        }
+
+        self.root = current;
+        self.next_index += 1;
+        self.member_count = self
+            .member_count
+            .checked_add(1)
+            .ok_or(VotingError::RegistryCountOverflow)?;
+        self.last_update_slot = slot;
+
+        self.root_history_index = (self.root_history_index + 1) % ROOT_HISTORY_SIZE as u8;
+        self.root_history[self.root_history_index as usize] = HistoricalRoot {
+            root: current,
+            leaf_count: self.next_index,
+            slot,
+        };
+        Ok(index)
+    }
+
+    /// Whether the registry's root is final: explicitly frozen, or a token
+    /// snapshot past its registration window (no leaf can be added to either)
+    fn is_frozen(&self, now: i64) -> bool {
+        match (self.status, self.eligibility) {
+            (RegistryStatus::Frozen, _) => true,
+            (
+                RegistryStatus::Open,
+                Eligibility::TokenSnapshot {
+                    registration_ends_at,
+                    ..
+                },
+            ) => now >= registration_ends_at,
+            _ => false,
+        }
+    }
+
+    /// Find a root in the recent root history (unused entries are all-zero)
+    fn historical_root(&self, root: &[u8; 32]) -> Option<&HistoricalRoot> {
+        self.root_history
+            .iter()
+            .find(|entry| entry.root != [0u8; 32] && entry.root == *root)
+    }
+}
+
+/// Insert a commitment into a registry and announce the new root
+fn register_leaf(registry: &mut Account<VoterRegistry>, commitment: [u8; 32]) -> Result<u64> {
+    require!(
+        registry.status == RegistryStatus::Open,
+        VotingError::RegistryNotOpen
+    );
+    let slot = Clock::get()?.slot;
+    let leaf_index = registry.insert(commitment, slot)?;
+
+    emit!(CommitmentRegistered {
+        registry: registry.key(),
+        leaf_index,
+        commitment,
+        root: registry.root,
+        member_count: registry.member_count,
+        slot,
+    });
+    Ok(leaf_index)
+}
+
+/// Move a registry one step along its Open -> Frozen -> Archived lifecycle
+fn set_registry_status(
+    registry: &mut Account<VoterRegistry>,
+    from: RegistryStatus,
+    to: RegistryStatus,
+) -> Result<()> {
+    require!(
+        registry.status == from,
+        VotingError::InvalidRegistryStatus
+    );
+    registry.status = to;
+    registry.last_update_slot = Clock::get()?.slot;
+
+    emit!(RegistryStatusChanged {
+        registry: registry.key(),
+        status: to,
+        root: registry.root,
+        member_count: registry.member_count,
+        slot: registry.last_update_slot,
+    });
+    msg!(
+        "Registry {} is now {:?}, root {:?}",
+        registry.registry_id,
+        to,
+        registry.root
+    );
+    Ok(())
+}
+
+/// Check a wallet's (wallet, balance) leaf against a token snapshot root
+///
+/// Leaves are Poseidon(wallet[0..16], wallet[16..32], balance) with each input
+/// left-padded to 32 bytes; the leaf index bits select left/right at each level.
+fn verify_holder_proof(
+    holders_root: &[u8; 32],
+    wallet: &Pubkey,
+    proof: &HolderProof,
+) -> Result<()> {
+    require!(
+        proof.siblings.len() <= MAX_SNAPSHOT_DEPTH,
+        VotingError::NotEligible
+    );
+
+    let wallet_bytes = wallet.to_bytes();
+    let mut wallet_hi = [0u8; 32];
+    wallet_hi[16..].copy_from_slice(&wallet_bytes[..16]);
+    let mut wallet_lo = [0u8; 32];
+    wallet_lo[16..].copy_from_slice(&wallet_bytes[16..]);
+    let mut balance = [0u8; 32];
+    balance[24..].copy_from_slice(&proof.balance.to_be_bytes());
+
+    let leaf = hashv(
+        Parameters::Bn254X5,
+        Endianness::BigEndian,
+        &[&wallet_hi, &wallet_lo, &balance],
+    )
+    .map(|hash| hash.to_bytes())
+    .map_err(|_| error!(VotingError::NotEligible))?;
+
+    require!(
+        compute_merkle_root(&leaf, proof.index.into(), &proof.siblings)? == *holders_root,
+        VotingError::NotEligible
+    );
+    Ok(())
+}
+
+/// Read the collection field, (verified, collection mint), of a Metaplex
+/// metadata account
+///
+/// Only the fields ahead of `collection` are decoded, so later additions to
+/// the metadata layout don't affect it. Returns None for malformed data or
+/// NFTs without a collection.
+fn metadata_collection(data: &[u8]) -> Option<(bool, Pubkey)> {
+    // Key::MetadataV1
+    if data.first() != Some(&4) {
+        return None;
+    }
+    // key, update_authority, mint
+    let data = &mut data.get(65..)?;
+
+    // name, symbol, uri
+    for _ in 0..3 {
+        String::deserialize(data).ok()?;
+    }
+    // seller_fee_basis_points, creators (address, verified, share)
+    u16::deserialize(data).ok()?;
+    Option::<Vec<(Pubkey, bool, u8)>>::deserialize(data).ok()?;
+    // primary_sale_happened, is_mutable, edition_nonce, token_standard
+    <(bool, bool)>::deserialize(data).ok()?;
+    <(Option<u8>, Option<u8>)>::deserialize(data).ok()?;
+
+    Option::<(bool, Pubkey)>::deserialize(data).ok()?
+}
+
+/// Root of a Merkle path; bit i of `index` set means the node is the right
+/// child at level i (same convention as the circuit's path_indices)
+fn compute_merkle_root(leaf: &[u8; 32], index: u64, siblings: &[[u8; 32]]) -> Result<[u8; 32]> {
+    let mut current = *leaf;
+    let mut index = index;
+    for sibling in siblings {
+        current = if index & 1 == 0 {
+            poseidon_hash(&current, sibling)?
+        } else {
+            poseidon_hash(sibling, &current)?
+        };
+        index >>= 1;
+    }
+    Ok(current)
+}
+
+/// Pick the root a ballot's proof must verify against
+///
+/// Proposals bound to a registry with `accept_recent_roots` take any root
+/// still in the registry's history that existed at the proposal's snapshot.
+/// Since the tree is append-only, such a root's members are a subset of the
+/// snapshot's; roots produced after the snapshot are rejected.
+fn resolve_voters_root(
+    proposal: &Proposal,
+    registry: Option<&Account<VoterRegistry>>,
+    claimed_root: Option<[u8; 32]>,
+) -> Result<[u8; 32]> {
+    let root = match claimed_root {
+        Some(root) if root != proposal.voters_root => root,
+        _ => return Ok(proposal.voters_root),
+    };
+
+    require!(proposal.accept_recent_roots, VotingError::RootNotAccepted);
+    let registry = registry.ok_or(VotingError::RegistryRequired)?;
+    require!(
+        proposal.registry == Some(registry.key()),
+        VotingError::RegistryMismatch
+    );
+    let entry = registry
+        .historical_root(&root)
+        .ok_or(VotingError::RootNotInHistory)?;
+    require!(
+        entry.leaf_count <= proposal.snapshot_leaf_count,
+        VotingError::RootAfterSnapshot
+    );
+    Ok(root)
+}
+
+/// Public inputs and proof of a ballot
+struct BallotSubmission<'a> {
+    nullifier: [u8; 32],
+    /// `vote` public input: option index, approval bitmask or packed ranking
+    vote: u32,
+    /// `votes` public input: per-option votes of vector ballots (zero otherwise)
+    votes: [u32; 8],
+    proof_data: &'a [u8],
+    voters_root: Option<[u8; 32]>,
+    group: u8,
+    weight: u64,
+    /// `ballot_digest` public input: digest of an encrypted ballot's
+    /// ciphertexts or a sealed ballot's commitment (zero otherwise)
+    ballot_digest: [u8; 32],
+    /// `revision` public input: which revision of the ballot this is (zero
+    /// when first cast), so a revise_vote proof can't be replayed
+    revision: u16,
+}
+
+/// Checks shared by every ballot type: voting window, prerequisite, weight,
+/// root selection and (when enabled) proof verification
+///
+/// The caller validates the vote itself and records the nullifier.
+fn admit_ballot(
+    proposal: &Proposal,
+    prerequisite: Option<&Account<Proposal>>,
+    registry: Option<&Account<VoterRegistry>>,
+    ballot: &BallotSubmission,
+) -> Result<()> {
+    // Check voting is still open
+    let clock = Clock::get()?;
+    require!(
+        clock.unix_timestamp < proposal.voting_ends_at,
+        VotingError::VotingEnded
+    );
+    require!(!proposal.is_finalized, VotingError::ProposalFinalized);
+    check_prerequisite(proposal, prerequisite)?;
+
+    require!(
+        proposal.reveals_weight() == (ballot.weight > 0),
+        VotingError::InvalidWeight
+    );
+
+    // Validate proof size
+    require!(
+        ballot.proof_data.len() <= MAX_PROOF_SIZE,
+        VotingError::ProofTooLarge
+    );
+
+    let voters_root = if proposal.voter_groups.is_empty() {
+        require!(ballot.group == 0, VotingError::InvalidVoterGroup);
+        resolve_voters_root(proposal, registry, ballot.voters_root)?
+    } else {
+        let group_root = proposal
+            .voter_groups
+            .get(ballot.group as usize)
+            .ok_or(VotingError::InvalidVoterGroup)?
+            .voters_root;
+        require!(
+            ballot.voters_root.is_none_or(|root| root == group_root),
+            VotingError::RootNotAccepted
+        );
+        group_root
+    };
+
+    // On-chain ZK proof verification
+    if verifying_key::VERIFICATION_ENABLED {
+        verify_groth16_proof(proposal, &voters_root, ballot)?;
+        msg!("ZK proof verified on-chain (~200k CU)");
+    } else {
+        // When verification is disabled, proofs are checked off-chain
+        // The nullifier PDA still prevents double voting
+        msg!(
+            "Proof received: {} bytes (off-chain verification mode)",
+            ballot.proof_data.len()
+        );
+    }
+    Ok(())
+}
+
+/// Poseidon hash of two BN254 field elements (big-endian), same as hash_2 in
+/// the circuit; fails if either input is not a canonical field element
+fn poseidon_hash(left: &[u8; 32], right: &[u8; 32]) -> Result<[u8; 32]> {
+    hashv(Parameters::Bn254X5, Endianness::BigEndian, &[left, right])
+        .map(|hash| hash.to_bytes())
+        .map_err(|_| error!(VotingError::InvalidCommitment))
+}
+
+/// Leaf of a weighted registry, hash(commitment, weight); same as
+/// compute_weighted_leaf in the circuit
+fn weighted_leaf(commitment: &[u8; 32], weight: u64) -> Result<[u8; 32]> {
+    let mut weight_bytes = [0u8; 32];
+    weight_bytes[24..].copy_from_slice(&weight.to_be_bytes());
+    poseidon_hash(commitment, &weight_bytes)
+}
+
+/// Sum of two G1 points (altbn254 addition syscall); fails on points that
+/// are not on the curve
+fn g1_add(left: &[u8; 64], right: &[u8; 64]) -> Result<[u8; 64]> {
+    let mut input = [0u8; 128];
+    input[..64].copy_from_slice(left);
+    input[64..].copy_from_slice(right);
+    alt_bn128_addition(&input)
+        .ok()
+        .and_then(|sum| sum.try_into().ok())
+        .ok_or_else(|| error!(VotingError::InvalidCurvePoint))
+}
+
+/// `scalar` * `point` (altbn254 multiplication syscall)
+fn g1_mul(point: &[u8; 64], scalar: &[u8; 32]) -> Result<[u8; 64]> {
+    let mut input = [0u8; 96];
+    input[..64].copy_from_slice(point);
+    input[64..].copy_from_slice(scalar);
+    alt_bn128_multiplication(&input)
+        .ok()
+        .and_then(|product| product.try_into().ok())
+        .ok_or_else(|| error!(VotingError::InvalidCurvePoint))
+}
+
+/// Negation of a G1 point: (x, p - y), with the identity (all zeros) mapped
+/// to itself
+fn g1_neg(point: &[u8; 64]) -> [u8; 64] {
+    if point == &[0u8; 64] {
+        return *point;
+    }
+    let y: [u8; 32] = point[32..].try_into().unwrap();
+    let mut negated = *point;
+    negated[32..].copy_from_slice(&sub_be(&BN254_FIELD_MODULUS, &y).0);
+    negated
+}
+
+/// a - b over 256-bit big-endian integers, and whether it borrowed (a < b)
+fn sub_be(a: &[u8; 32], b: &[u8; 32]) -> ([u8; 32], bool) {
+    let mut out = [0u8; 32];
+    let mut borrow = false;
+    for i in (0..32).rev() {
+        let (diff, borrow_a) = a[i].overflowing_sub(b[i]);
+        let (diff, borrow_b) = diff.overflowing_sub(borrow as u8);
+        out[i] = diff;
+        borrow = borrow_a || borrow_b;
+    }
+    (out, borrow)
+}
+
+/// (a + b) mod r for canonical scalars a, b < r
+fn scalar_add(a: &[u8; 32], b: &[u8; 32]) -> [u8; 32] {
+    let mut sum = [0u8; 32];
+    let mut carry = false;
+    for i in (0..32).rev() {
+        let (total, carry_a) = a[i].overflowing_add(b[i]);
+        let (total, carry_b) = total.overflowing_add(carry as u8);
+        sum[i] = total;
+        carry = carry_a || carry_b;
+    }
+    // a + b < 2r < 2^256, so there is no carry out and at most one reduction
+    match sub_be(&sum, &BN254_SCALAR_ORDER) {
+        (reduced, false) => reduced,
+        (_, true) => sum,
+    }
+}
+
+/// Whether a 32-byte big-endian value is a canonical scalar (< r)
+fn is_scalar(value: &[u8; 32]) -> bool {
+    sub_be(value, &BN254_SCALAR_ORDER).1
+}
+
+/// A u64 as a big-endian scalar
+fn scalar(value: u64) -> [u8; 32] {
+    let mut bytes = [0u8; 32];
+    bytes[24..].copy_from_slice(&value.to_be_bytes());
+    bytes
+}
+
+/// sha256 of `parts` truncated to 253 bits, so the result is a scalar and a
+/// field element; used for Fiat-Shamir challenges and ballot digests
+fn hash_to_scalar(parts: &[&[u8]]) -> [u8; 32] {
+    let mut digest = solana_sha256_hasher::hashv(parts).to_bytes();
+    digest[0] &= 0x1f;
+    digest
+}
+
+/// Split an ElGamal ciphertext into its two G1 points (c1, c2)
+fn ciphertext_points(ciphertext: &[u8; 128]) -> ([u8; 64], [u8; 64]) {
+    let c1 = ciphertext[..64].try_into().unwrap();
+    let c2 = ciphertext[64..].try_into().unwrap();
+    (c1, c2)
+}
+
+/// Component-wise sum of two ciphertexts, an encryption of the sum of their
+/// plaintexts
+fn add_ciphertexts(left: &[u8; 128], right: &[u8; 128]) -> Result<[u8; 128]> {
+    let (left_c1, left_c2) = ciphertext_points(left);
+    let (right_c1, right_c2) = ciphertext_points(right);
+    let mut sum = [0u8; 128];
+    sum[..64].copy_from_slice(&g1_add(&left_c1, &right_c1)?);
+    sum[64..].copy_from_slice(&g1_add(&left_c2, &right_c2)?);
+    Ok(sum)
+}
+
+/// Lagrange coefficients at 0 for the committee members `signers` (member j
+/// holds the share at j + 1), scaled to integers: returns the common
+/// denominator d and, per signer, |d * lambda_j| and whether lambda_j < 0
+fn lagrange_coefficients(signers: &[u8]) -> (u64, Vec<(u64, bool)>) {
+    let fractions: Vec<(u64, u64, bool)> = signers
+        .iter()
+        .map(|&signer| {
+            let x = signer as i64 + 1;
+            let mut numerator = 1u64;
+            let mut denominator = 1i64;
+            for &other in signers.iter().filter(|&&other| other != signer) {
+                let other_x = other as i64 + 1;
+                numerator *= other_x as u64;
+                denominator *= other_x - x;
+            }
+            (numerator, denominator.unsigned_abs(), denominator < 0)
+        })
+        .collect();
+
+    // With at most 8 members every denominator divides 7!, so this stays small
+    let common = fractions.iter().fold(1u64, |lcm, &(_, denominator, _)| {
+        lcm / gcd(lcm, denominator) * denominator
+    });
+    let coefficients = fractions
+        .iter()
+        .map(|&(numerator, denominator, negative)| (common / denominator * numerator, negative))
+        .collect();
+    (common, coefficients)
+}
+
+fn gcd(a: u64, b: u64) -> u64 {
+    if b == 0 {
+        a
+    } else {
+        gcd(b, a % b)
+    }
+}
+
+/// f(x)*G at x = member + 1 for the Feldman commitments a_t*G of a
+/// polynomial f, by Horner's rule
+fn feldman_evaluation(commitments: &[[u8; 64]], member: u8) -> Result<[u8; 64]> {
+    let x = scalar(member as u64 + 1);
+    let mut evaluation = [0u8; 64];
+    for commitment in commitments.iter().rev() {
+        evaluation = g1_add(&g1_mul(&evaluation, &x)?, commitment)?;
+    }
+    Ok(evaluation)
+}
+
+/// Digest of a dealing's commitments, kept so they can be checked when
+/// answering a complaint or disqualifying the dealer
+fn dealing_hash(commitments: &[[u8; 64]]) -> [u8; 32] {
+    let parts: Vec<&[u8]> = commitments
+        .iter()
+        .map(|commitment| &commitment[..])
+        .collect();
+    hash_to_scalar(&parts)
+}
+
+/// `ballot_digest` public input of an encrypted ballot
+fn ballot_digest(ciphertexts: &[[u8; 128]]) -> [u8; 32] {
+    let parts: Vec<&[u8]> = ciphertexts
+        .iter()
+        .map(|ciphertext| &ciphertext[..])
+        .collect();
+    hash_to_scalar(&parts)
+}
+
+/// Commitments of a Chaum-Pedersen proof that log_g1(h1) == log_g2(h2),
+/// recomputed from its challenge c and response z: (z*g1 - c*h1, z*g2 - c*h2)
+fn dleq_commitments(
+    g1: &[u8; 64],
+    h1: &[u8; 64],
+    g2: &[u8; 64],
+    h2: &[u8; 64],
+    challenge: &[u8; 32],
+    response: &[u8; 32],
+) -> Result<([u8; 64], [u8; 64])> {
+    let a = g1_add(&g1_mul(g1, response)?, &g1_neg(&g1_mul(h1, challenge)?))?;
+    let b = g1_add(&g1_mul(g2, response)?, &g1_neg(&g1_mul(h2, challenge)?))?;
+    Ok((a, b))
+}
+
+/// Check a Chaum-Pedersen proof that log_g1(h1) == log_g2(h2); `context`
+/// binds the proof to what it is about
+fn dleq_holds(
+    g1: &[u8; 64],
+    h1: &[u8; 64],
+    g2: &[u8; 64],
+    h2: &[u8; 64],
+    proof: &DleqProof,
+    context: &[&[u8]],
+) -> Result<bool> {
+    let (a, b) = dleq_commitments(g1, h1, g2, h2, &proof.challenge, &proof.response)?;
+    let mut transcript = context.to_vec();
+    transcript.extend_from_slice(&[g1, h1, g2, h2, &a, &b]);
+    Ok(hash_to_scalar(&transcript) == proof.challenge)
+}
+
+/// Check a disjunctive Chaum-Pedersen proof that `ciphertext` encrypts 0 or
+/// 1 under `key`: branch m shows log_G(c1) == log_key(c2 - m*G), and the
+/// branch challenges must add up to the transcript's challenge, so at most
+/// one of them can be simulated
+fn verify_bit_proof(
+    key: &[u8; 64],
+    ciphertext: &[u8; 128],
+    proof: &BitProof,
+    context: &[&[u8]],
+) -> Result<bool> {
+    if !proof.challenges.iter().all(is_scalar) {
+        return Ok(false);
+    }
+    let (c1, c2) = ciphertext_points(ciphertext);
+    let c2_minus_one = g1_add(&c2, &g1_neg(&G1_GENERATOR))?;
+    let (a0, b0) = dleq_commitments(
+        &G1_GENERATOR,
+        &c1,
+        key,
+        &c2,
+        &proof.challenges[0],
+        &proof.responses[0],
+    )?;
+    let (a1, b1) = dleq_commitments(
+        &G1_GENERATOR,
+        &c1,
+        key,
+        &c2_minus_one,
+        &proof.challenges[1],
+        &proof.responses[1],
+    )?;
+    let mut transcript = context.to_vec();
+    transcript.extend_from_slice(&[key, ciphertext, &a0, &b0, &a1, &b1]);
+    Ok(hash_to_scalar(&transcript) == scalar_add(&proof.challenges[0], &proof.challenges[1]))
+}
+
+/// Ensure a proposal's prerequisite, if it has one, was finalized with its
+/// approval option winning
+fn check_prerequisite(
+    proposal: &Proposal,
+    prerequisite: Option<&Account<Proposal>>,
+) -> Result<()> {
+    let Some(required) = proposal.prerequisite else {
+        return Ok(());
+    };
+    let prerequisite = prerequisite.ok_or(VotingError::PrerequisiteMissing)?;
+    require_keys_eq!(
+        prerequisite.key(),
+        required,
+        VotingError::PrerequisiteMissing
+    );
+    require!(
+        prerequisite.is_finalized,
+        VotingError::PrerequisiteNotFinalized
+    );
+    require!(prerequisite.passed(), VotingError::PrerequisiteNotPassed);
+    Ok(())
+}
+
+/// Find the account an action refers to among the instruction's remaining accounts
+fn action_account<'a, 'info>(
+    accounts: &'a [AccountInfo<'info>],
+    key: &Pubkey,
+) -> Result<&'a AccountInfo<'info>> {
+    accounts
+        .iter()
+        .find(|account| account.key == key)
+        .ok_or_else(|| error!(VotingError::ActionAccountMissing))
+}
+
+// ============================================================================
+// ZK Proof Verification
+// ============================================================================
+
+/// Verify a Groth16 proof using Solana's altbn254 precompiles
+///
+/// Expected proof format (256 bytes):
+/// - bytes 0-63: proof_a (G1, negated, big-endian)
+/// - bytes 64-191: proof_b (G2, big-endian)
+/// - bytes 192-255: proof_c (G1, big-endian)
+///
+/// When groth16-solana is enabled, this uses ~200k compute units.
+fn verify_groth16_proof(
+    proposal: &Proposal,
+    voters_root: &[u8; 32],
+    ballot: &BallotSubmission,
+) -> Result<()> {
+    let proof_data = ballot.proof_data;
+
+    // Validate proof size
+    require!(proof_data.len() >= 256, VotingError::InvalidProof);
+
+    // Extract proof components
+    let proof_a: [u8; 64] = proof_data[0..64]
+        .try_into()
+        .map_err(|_| VotingError::InvalidProof)?;
+    let proof_b: [u8; 128] = proof_data[64..192]
+        .try_into()
+        .map_err(|_| VotingError::InvalidProof)?;
+    let proof_c: [u8; 64] = proof_data[192..256]
+        .try_into()
+        .map_err(|_| VotingError::InvalidProof)?;
+
+    // Prepare public inputs (32 bytes each, big-endian)
+    // Order must match circuit: voters_root, nullifier, proposal_id, vote, num_options,
+    // revocation_root, weight, ballot_mode, votes, point_budget, ballot_digest, group,
+    // revision
+    let mut proposal_id_bytes = [0u8; 32];
+    proposal_id_bytes[24..32].copy_from_slice(&proposal.proposal_id.to_be_bytes());
+
+    let mut vote_bytes = [0u8; 32];
+    vote_bytes[28..32].copy_from_slice(&ballot.vote.to_be_bytes());
+
+    let mut num_options_bytes = [0u8; 32];
+    num_options_bytes[31] = proposal.num_options;
+
+    let mut weight_bytes = [0u8; 32];
+    weight_bytes[24..32].copy_from_slice(&ballot.weight.to_be_bytes());
+
+    let mut ballot_mode_bytes = [0u8; 32];
+    ballot_mode_bytes[31] = proposal.ballot_mode.code();
+
+    // Convert public inputs to fixed-size array format
+    let mut public_inputs_arr: [[u8; 32]; PUBLIC_INPUT_COUNT] = [[0u8; 32]; PUBLIC_INPUT_COUNT];
+    public_inputs_arr[0].copy_from_slice(voters_root);
+    public_inputs_arr[1].copy_from_slice(&ballot.nullifier);
+    public_inputs_arr[2].copy_from_slice(&proposal_id_bytes);
+    public_inputs_arr[3].copy_from_slice(&vote_bytes);
+    public_inputs_arr[4].copy_from_slice(&num_options_bytes);
+    public_inputs_arr[5].copy_from_slice(&proposal.revocation_root);
+    public_inputs_arr[6].copy_from_slice(&weight_bytes);
+    public_inputs_arr[7].copy_from_slice(&ballot_mode_bytes);
+    for (input, option_votes) in public_inputs_arr[8..16].iter_mut().zip(ballot.votes.iter()) {
+        input[28..32].copy_from_slice(&option_votes.to_be_bytes());
+    }
+    let mut point_budget_bytes = [0u8; 32];
+    point_budget_bytes[28..32].copy_from_slice(&proposal.ballot_mode.point_budget().to_be_bytes());
+    public_inputs_arr[16].copy_from_slice(&point_budget_bytes);
+    public_inputs_arr[17].copy_from_slice(&ballot.ballot_digest);
+    public_inputs_arr[18][31] = ballot.group;
+    public_inputs_arr[19][30..32].copy_from_slice(&ballot.revision.to_be_bytes());
+
+    // Construct verifying key
+    let vk = Groth16Verifyingkey {
+        nr_pubinputs: PUBLIC_INPUT_COUNT,
+        vk_alpha_g1: verifying_key::ALPHA,
+        vk_beta_g2: verifying_key::BETA,
+        vk_gamme_g2: verifying_key::GAMMA,
+        vk_delta_g2: verifying_key::DELTA,
+        vk_ic: &verifying_key::IC,
+    };
+
+    // Groth16 on-chain verification using Solana's altbn254 precompiles
+    let mut verifier = Groth16Verifier::new(
+        &proof_a,
+        &proof_b,
+        &proof_c,
+        &public_inputs_arr,
+        &vk,
+    )
+    .map_err(|_| VotingError::InvalidProof)?;
+
+    verifier.verify().map_err(|_| VotingError::InvalidProof)?;
+
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct InitializeConfig<'info> {
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + ProgramConfig::INIT_SPACE,
+        seeds = [b"config"],
+        bump
+    )]
+    pub config: Account<'info, ProgramConfig>,
+
+    #[account(constraint = program.programdata_address()? == Some(program_data.key()))]
+    pub program: Program<'info, crate::program::VotingProgram>,
+
+    #[account(
+        constraint = program_data.upgrade_authority_address == Some(authority.key())
+            @ VotingError::Unauthorized
+    )]
+    pub program_data: Account<'info, ProgramData>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct SetPaused<'info> {
+    #[account(
+        mut,
+        seeds = [b"config"],
+        bump = config.bump,
+        has_one = guardian @ VotingError::Unauthorized
+    )]
+    pub config: Account<'info, ProgramConfig>,
+
+    pub guardian: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct ProposeGuardianTransfer<'info> {
+    #[account(
+        mut,
+        seeds = [b"config"],
+        bump = config.bump,
+        has_one = guardian @ VotingError::Unauthorized
+    )]
+    pub config: Account<'info, ProgramConfig>,
+
+    pub guardian: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct AcceptGuardianTransfer<'info> {
+    #[account(
+        mut,
+        seeds = [b"config"],
+        bump = config.bump,
+        constraint = config.pending_guardian == Some(new_guardian.key())
+            @ VotingError::NotPendingAuthority
+    )]
+    pub config: Account<'info, ProgramConfig>,
+
+    pub new_guardian: Signer<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(dao_id: u64)]
+pub struct CreateDao<'info> {
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + Dao::INIT_SPACE,
+        seeds = [b"dao", dao_id.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub dao: Account<'info, Dao>,
+
+    #[account(seeds = [b"treasury", dao.key().as_ref()], bump)]
+    pub treasury: SystemAccount<'info>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+        constraint = !config.paused @ VotingError::ProgramPaused
+    )]
+    pub config: Account<'info, ProgramConfig>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(registry_id: u64)]
+pub struct CreateVoterRegistry<'info> {
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + VoterRegistry::INIT_SPACE,
+        seeds = [b"registry", registry_id.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub registry: Box<Account<'info, VoterRegistry>>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+        constraint = !config.paused @ VotingError::ProgramPaused
+    )]
+    pub config: Account<'info, ProgramConfig>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct RegisterCommitment<'info> {
+    #[account(
+        mut,
+        has_one = authority @ VotingError::Unauthorized
+    )]
+    pub registry: Box<Account<'info, VoterRegistry>>,
+
+    pub authority: Signer<'info>,
+
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+        constraint = !config.paused @ VotingError::ProgramPaused
+    )]
+    pub config: Account<'info, ProgramConfig>,
+}
+
+#[derive(Accounts)]
+pub struct RevokeCommitment<'info> {
+    #[account(
+        mut,
+        has_one = authority @ VotingError::Unauthorized
+    )]
+    pub registry: Box<Account<'info, VoterRegistry>>,
+
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct SetRegistryStatus<'info> {
+    #[account(
+        mut,
+        has_one = authority @ VotingError::Unauthorized
+    )]
+    pub registry: Box<Account<'info, VoterRegistry>>,
+
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(wallet: Pubkey)]
+pub struct AddToAllowlist<'info> {
+    #[account(has_one = authority @ VotingError::Unauthorized)]
+    pub registry: Box<Account<'info, VoterRegistry>>,
+
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + AllowlistEntry::INIT_SPACE,
+        seeds = [b"allowlist", registry.key().as_ref(), wallet.as_ref()],
+        bump
+    )]
+    pub allowlist_entry: Account<'info, AllowlistEntry>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+        constraint = !config.paused @ VotingError::ProgramPaused
+    )]
+    pub config: Account<'info, ProgramConfig>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct RegisterWalletCommitment<'info> {
+    #[account(mut)]
+    pub registry: Box<Account<'info, VoterRegistry>>,
+
+    #[account(
+        init,
+        payer = wallet,
+        space = 8 + VoterCommitment::INIT_SPACE,
+        seeds = [b"commitment", registry.key().as_ref(), wallet.key().as_ref()],
+        bump
+    )]
+    pub voter_commitment: Account<'info, VoterCommitment>,
+
+    /// Required for allowlist registries
+    #[account(
+        seeds = [b"allowlist", registry.key().as_ref(), wallet.key().as_ref()],
+        bump = allowlist_entry.bump
+    )]
+    pub allowlist_entry: Option<Account<'info, AllowlistEntry>>,
+
+    #[account(mut)]
+    pub wallet: Signer<'info>,
+
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+        constraint = !config.paused @ VotingError::ProgramPaused
+    )]
+    pub config: Account<'info, ProgramConfig>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct RegisterNftCommitment<'info> {
+    #[account(mut)]
+    pub registry: Box<Account<'info, VoterRegistry>>,
+
+    #[account(
+        init,
+        payer = wallet,
+        space = 8 + NftCommitment::INIT_SPACE,
+        seeds = [b"nft", registry.key().as_ref(), nft_mint.key().as_ref()],
+        bump
+    )]
+    pub nft_commitment: Account<'info, NftCommitment>,
+
+    pub nft_mint: Account<'info, Mint>,
+
+    #[account(
+        constraint = token_account.owner == wallet.key()
+            && token_account.mint == nft_mint.key()
+            && token_account.amount == 1 @ VotingError::NotEligible
+    )]
+    pub token_account: Account<'info, TokenAccount>,
+
+    /// CHECK: Metaplex metadata PDA of the mint; parsed in the handler
+    #[account(
+        seeds = [b"metadata", METADATA_PROGRAM_ID.as_ref(), nft_mint.key().as_ref()],
+        seeds::program = METADATA_PROGRAM_ID,
+        bump,
+        owner = METADATA_PROGRAM_ID @ VotingError::NotEligible
+    )]
+    pub metadata: UncheckedAccount<'info>,
+
+    #[account(mut)]
+    pub wallet: Signer<'info>,
+
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+        constraint = !config.paused @ VotingError::ProgramPaused
+    )]
+    pub config: Account<'info, ProgramConfig>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(proposal_id: u64)]
+pub struct CreateProposal<'info> {
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + Proposal::INIT_SPACE,
+        seeds = [b"proposal", proposal_id.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub proposal: Account<'info, Proposal>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    #[account(has_one = authority @ VotingError::Unauthorized)]
+    pub dao: Option<Account<'info, Dao>>,
+
+    pub prerequisite: Option<Account<'info, Proposal>>,
+
+    pub registry: Option<Box<Account<'info, VoterRegistry>>>,
+
+    /// Required for (and only for) Encrypted proposals
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + EncryptedTally::INIT_SPACE,
+        seeds = [b"encrypted_tally", proposal.key().as_ref()],
+        bump
+    )]
+    pub encrypted_tally: Option<Box<Account<'info, EncryptedTally>>>,
+
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+        constraint = !config.paused @ VotingError::ProgramPaused
+    )]
+    pub config: Account<'info, ProgramConfig>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct AddProposalAction<'info> {
+    #[account(
+        mut,
+        has_one = authority
+    )]
+    pub proposal: Account<'info, Proposal>,
+
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + ProposalAction::INIT_SPACE,
+        seeds = [b"action", proposal.key().as_ref(), &[proposal.action_count]],
+        bump
+    )]
+    pub action: Account<'info, ProposalAction>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+        constraint = !config.paused @ VotingError::ProgramPaused
+    )]
+    pub config: Account<'info, ProgramConfig>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(
+    nullifier: [u8; 32],
+    vote: u8,
+    proof_data: Vec<u8>,
+    voters_root: Option<[u8; 32]>,
+    group: u8
+)]
+pub struct CastVote<'info> {
+    #[account(mut)]
+    pub proposal: Account<'info, Proposal>,
+
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + NullifierAccount::INIT_SPACE,
+        seeds = [b"nullifier", proposal.key().as_ref(), &[group], nullifier.as_ref()],
+        bump
+    )]
+    pub nullifier_account: Account<'info, NullifierAccount>,
+
+    /// Required when the proposal has a prerequisite
+    pub prerequisite: Option<Account<'info, Proposal>>,
+
+    /// Required when voting against an older registry root
+    pub registry: Option<Box<Account<'info, VoterRegistry>>>,
+
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+        constraint = !config.paused @ VotingError::ProgramPaused
+    )]
+    pub config: Account<'info, ProgramConfig>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(
+    nullifier: [u8; 32],
+    votes: [u32; 8],
+    proof_data: Vec<u8>,
+    voters_root: Option<[u8; 32]>,
+    group: u8,
+    weight: u64
+)]
+pub struct CastVectorVote<'info> {
+    #[account(mut)]
+    pub proposal: Account<'info, Proposal>,
+
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + NullifierAccount::INIT_SPACE,
+        seeds = [b"nullifier", proposal.key().as_ref(), &[group], nullifier.as_ref()],
+        bump
+    )]
+    pub nullifier_account: Account<'info, NullifierAccount>,
+
+    /// Required when the proposal has a prerequisite
+    pub prerequisite: Option<Account<'info, Proposal>>,
+
+    /// Required when voting against an older registry root
+    pub registry: Option<Box<Account<'info, VoterRegistry>>>,
+
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+        constraint = !config.paused @ VotingError::ProgramPaused
+    )]
+    pub config: Account<'info, ProgramConfig>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(nullifier: [u8; 32])]
+pub struct CastRankedVote<'info> {
+    #[account(mut)]
+    pub proposal: Account<'info, Proposal>,
+
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + NullifierAccount::INIT_SPACE,
+        seeds = [b"nullifier", proposal.key().as_ref(), &[0], nullifier.as_ref()],
+        bump
+    )]
+    pub nullifier_account: Account<'info, NullifierAccount>,
+
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + RankedBallot::INIT_SPACE,
+        seeds = [b"ballot", proposal.key().as_ref(), nullifier.as_ref()],
+        bump
+    )]
+    pub ballot: Account<'info, RankedBallot>,
+
+    /// Required when the proposal has a prerequisite
+    pub prerequisite: Option<Account<'info, Proposal>>,
+
+    /// Required when voting against an older registry root
+    pub registry: Option<Box<Account<'info, VoterRegistry>>>,
+
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+        constraint = !config.paused @ VotingError::ProgramPaused
+    )]
+    pub config: Account<'info, ProgramConfig>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(nullifier: [u8; 32])]
+pub struct OpenEncryptedBallot<'info> {
+    pub proposal: Box<Account<'info, Proposal>>,
+
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + EncryptedBallot::INIT_SPACE,
+        seeds = [
+            b"encrypted_ballot",
+            proposal.key().as_ref(),
+            nullifier.as_ref(),
+            payer.key().as_ref()
+        ],
+        bump
+    )]
+    pub ballot: Box<Account<'info, EncryptedBallot>>,
+
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+        constraint = !config.paused @ VotingError::ProgramPaused
+    )]
+    pub config: Account<'info, ProgramConfig>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(nullifier: [u8; 32])]
+pub struct PostEncryptedOptions<'info> {
+    pub proposal: Box<Account<'info, Proposal>>,
+
+    #[account(
+        mut,
+        seeds = [
+            b"encrypted_ballot",
+            proposal.key().as_ref(),
+            nullifier.as_ref(),
+            payer.key().as_ref()
+        ],
+        bump = ballot.bump
+    )]
+    pub ballot: Box<Account<'info, EncryptedBallot>>,
+
+    pub payer: Signer<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(nullifier: [u8; 32])]
+pub struct CastEncryptedVote<'info> {
+    #[account(mut)]
+    pub proposal: Box<Account<'info, Proposal>>,
+
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + NullifierAccount::INIT_SPACE,
+        seeds = [b"nullifier", proposal.key().as_ref(), &[0], nullifier.as_ref()],
+        bump
+    )]
+    pub nullifier_account: Account<'info, NullifierAccount>,
+
+    /// Closed to the payer once the ballot is tallied
+    #[account(
+        mut,
+        close = payer,
+        seeds = [
+            b"encrypted_ballot",
+            proposal.key().as_ref(),
+            nullifier.as_ref(),
+            payer.key().as_ref()
+        ],
+        bump = ballot.bump
+    )]
+    pub ballot: Box<Account<'info, EncryptedBallot>>,
+
+    #[account(
+        mut,
+        seeds = [b"encrypted_tally", proposal.key().as_ref()],
+        bump = encrypted_tally.bump
+    )]
+    pub encrypted_tally: Box<Account<'info, EncryptedTally>>,
+
+    /// Required when the proposal has a prerequisite
+    pub prerequisite: Option<Account<'info, Proposal>>,
+
+    /// Required when voting against an older registry root
+    pub registry: Option<Box<Account<'info, VoterRegistry>>>,
+
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+        constraint = !config.paused @ VotingError::ProgramPaused
+    )]
+    pub config: Account<'info, ProgramConfig>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(nullifier: [u8; 32])]
+pub struct CloseEncryptedBallot<'info> {
+    pub proposal: Box<Account<'info, Proposal>>,
+
+    #[account(
+        mut,
+        close = payer,
+        seeds = [
+            b"encrypted_ballot",
+            proposal.key().as_ref(),
+            nullifier.as_ref(),
+            payer.key().as_ref()
+        ],
+        bump = ballot.bump
+    )]
+    pub ballot: Box<Account<'info, EncryptedBallot>>,
+
+    #[account(mut)]
+    pub payer: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct DecryptTally<'info> {
+    #[account(mut)]
+    pub proposal: Box<Account<'info, Proposal>>,
+
+    #[account(
+        mut,
+        seeds = [b"encrypted_tally", proposal.key().as_ref()],
+        bump = encrypted_tally.bump
+    )]
+    pub encrypted_tally: Box<Account<'info, EncryptedTally>>,
+}
+
+#[derive(Accounts)]
+pub struct SetDecryptionCommittee<'info> {
+    #[account(mut, has_one = authority)]
+    pub proposal: Box<Account<'info, Proposal>>,
+
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + DecryptionCommittee::INIT_SPACE,
+        seeds = [b"committee", proposal.key().as_ref()],
+        bump
+    )]
+    pub committee: Box<Account<'info, DecryptionCommittee>>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+        constraint = !config.paused @ VotingError::ProgramPaused
+    )]
+    pub config: Account<'info, ProgramConfig>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct SubmitCommitteeDealing<'info> {
+    pub proposal: Box<Account<'info, Proposal>>,
+
+    #[account(
+        mut,
+        seeds = [b"committee", proposal.key().as_ref()],
+        bump = committee.bump
+    )]
+    pub committee: Box<Account<'info, DecryptionCommittee>>,
+
+    pub member: Signer<'info>,
+
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+        constraint = !config.paused @ VotingError::ProgramPaused
+    )]
+    pub config: Account<'info, ProgramConfig>,
+}
+
+#[derive(Accounts)]
+pub struct FileComplaint<'info> {
+    pub proposal: Box<Account<'info, Proposal>>,
+
+    #[account(
+        mut,
+        seeds = [b"committee", proposal.key().as_ref()],
+        bump = committee.bump
+    )]
+    pub committee: Box<Account<'info, DecryptionCommittee>>,
+
+    pub member: Signer<'info>,
+
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+        constraint = !config.paused @ VotingError::ProgramPaused
+    )]
+    pub config: Account<'info, ProgramConfig>,
+}
+
+#[derive(Accounts)]
+pub struct AnswerComplaint<'info> {
+    pub proposal: Box<Account<'info, Proposal>>,
+
+    #[account(
+        mut,
+        seeds = [b"committee", proposal.key().as_ref()],
+        bump = committee.bump
+    )]
+    pub committee: Box<Account<'info, DecryptionCommittee>>,
+
+    pub dealer: Signer<'info>,
+
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+        constraint = !config.paused @ VotingError::ProgramPaused
+    )]
+    pub config: Account<'info, ProgramConfig>,
+}
+
+#[derive(Accounts)]
+pub struct DisqualifyDealer<'info> {
+    pub proposal: Box<Account<'info, Proposal>>,
+
+    #[account(
+        mut,
+        seeds = [b"committee", proposal.key().as_ref()],
+        bump = committee.bump
+    )]
+    pub committee: Box<Account<'info, DecryptionCommittee>>,
+}
+
+#[derive(Accounts)]
+pub struct FixCommitteeKey<'info> {
+    #[account(mut)]
+    pub proposal: Box<Account<'info, Proposal>>,
+
+    #[account(
+        mut,
+        seeds = [b"committee", proposal.key().as_ref()],
+        bump = committee.bump
+    )]
+    pub committee: Box<Account<'info, DecryptionCommittee>>,
+}
+
+#[derive(Accounts)]
+pub struct SubmitDecryptionShare<'info> {
+    pub proposal: Box<Account<'info, Proposal>>,
+
+    #[account(
+        seeds = [b"encrypted_tally", proposal.key().as_ref()],
+        bump = encrypted_tally.bump
+    )]
+    pub encrypted_tally: Box<Account<'info, EncryptedTally>>,
+
+    #[account(
+        mut,
+        seeds = [b"committee", proposal.key().as_ref()],
+        bump = committee.bump
+    )]
+    pub committee: Box<Account<'info, DecryptionCommittee>>,
+}
+
+#[derive(Accounts)]
+pub struct CombineDecryptionShares<'info> {
+    #[account(mut)]
+    pub proposal: Box<Account<'info, Proposal>>,
+
+    #[account(
+        mut,
+        seeds = [b"encrypted_tally", proposal.key().as_ref()],
+        bump = encrypted_tally.bump
+    )]
+    pub encrypted_tally: Box<Account<'info, EncryptedTally>>,
+
+    #[account(
+        seeds = [b"committee", proposal.key().as_ref()],
+        bump = committee.bump
+    )]
+    pub committee: Box<Account<'info, DecryptionCommittee>>,
+}
+
+#[derive(Accounts)]
+#[instruction(
+    nullifier: [u8; 32],
+    commitment: [u8; 32],
+    proof_data: Vec<u8>,
+    voters_root: Option<[u8; 32]>,
+    group: u8
+)]
+pub struct CastSealedVote<'info> {
+    #[account(mut)]
+    pub proposal: Account<'info, Proposal>,
+
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + NullifierAccount::INIT_SPACE,
+        seeds = [b"nullifier", proposal.key().as_ref(), &[group], nullifier.as_ref()],
+        bump
+    )]
+    pub nullifier_account: Account<'info, NullifierAccount>,
+
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + SealedBallot::INIT_SPACE,
+        seeds = [
+            b"sealed_ballot",
+            proposal.key().as_ref(),
+            &[group],
+            nullifier.as_ref()
+        ],
+        bump
+    )]
+    pub ballot: Account<'info, SealedBallot>,
+
+    /// Required when the proposal has a prerequisite
+    pub prerequisite: Option<Account<'info, Proposal>>,
+
+    /// Required when voting against an older registry root
+    pub registry: Option<Box<Account<'info, VoterRegistry>>>,
+
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+        constraint = !config.paused @ VotingError::ProgramPaused
+    )]
+    pub config: Account<'info, ProgramConfig>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(nullifier: [u8; 32], group: u8)]
+pub struct RevealVote<'info> {
+    #[account(mut)]
+    pub proposal: Account<'info, Proposal>,
+
+    #[account(
+        mut,
+        seeds = [
+            b"sealed_ballot",
+            proposal.key().as_ref(),
+            &[group],
+            nullifier.as_ref()
+        ],
+        bump = ballot.bump
+    )]
+    pub ballot: Account<'info, SealedBallot>,
+}
+
+#[derive(Accounts)]
+pub struct RevealTally<'info> {
+    #[account(mut)]
+    pub proposal: Account<'info, Proposal>,
+}
+
+#[derive(Accounts)]
+#[instruction(
+    nullifier: [u8; 32],
+    vote: u8,
+    votes: [u32; 8],
+    proof_data: Vec<u8>,
+    voters_root: Option<[u8; 32]>,
+    group: u8
+)]
+pub struct ReviseVote<'info> {
+    #[account(mut)]
+    pub proposal: Account<'info, Proposal>,
+
+    #[account(
+        mut,
+        seeds = [b"nullifier", proposal.key().as_ref(), &[group], nullifier.as_ref()],
+        bump = nullifier_account.bump
+    )]
+    pub nullifier_account: Account<'info, NullifierAccount>,
+
+    /// Required when the proposal has a prerequisite
+    pub prerequisite: Option<Account<'info, Proposal>>,
+
+    /// Required when voting against an older registry root
+    pub registry: Option<Box<Account<'info, VoterRegistry>>>,
+
+    pub payer: Signer<'info>,
+
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+        constraint = !config.paused @ VotingError::ProgramPaused
+    )]
+    pub config: Account<'info, ProgramConfig>,
+}
+
+#[derive(Accounts)]
+pub struct TabulateIrv<'info> {
+    #[account(mut)]
+    pub proposal: Account<'info, Proposal>,
+}
+
+#[derive(Accounts)]
+pub struct FinalizeProposal<'info> {
+    #[account(
+        mut,
+        has_one = authority
+    )]
+    pub proposal: Account<'info, Proposal>,
+
+    /// Required for Encrypted proposals
+    #[account(
+        seeds = [b"encrypted_tally", proposal.key().as_ref()],
+        bump = encrypted_tally.bump
+    )]
+    pub encrypted_tally: Option<Box<Account<'info, EncryptedTally>>>,
 
-    // Extract proof components
-    let proof_a: [u8; 64] = proof_data[0..64]
-        .try_into()
-        .map_err(|_| VotingError::InvalidProof)?;
-    let proof_b: [u8; 128] = proof_data[64..192]
-        .try_into()
-        .map_err(|_| VotingError::InvalidProof)?;
-    let proof_c: [u8; 64] = proof_data[192..256]
-        .try_into()
-        .map_err(|_| VotingError::InvalidProof)?;
+    pub authority: Signer<'info>,
+}
 
-    // Prepare public inputs (32 bytes each, big-endian)
-    // Order must match circuit: voters_root, nullifier, proposal_id, vote, num_options
-    let mut proposal_id_bytes = [0u8; 32];
-    proposal_id_bytes[24..32].copy_from_slice(&proposal_id.to_be_bytes());
+#[derive(Accounts)]
+pub struct ExecuteAction<'info> {
+    #[account(constraint = proposal.dao == Some(dao.key()) @ VotingError::DaoMismatch)]
+    pub proposal: Account<'info, Proposal>,
 
-    let mut vote_bytes = [0u8; 32];
-    vote_bytes[31] = vote;
+    pub dao: Account<'info, Dao>,
 
-    let mut num_options_bytes = [0u8; 32];
-    num_options_bytes[31] = num_options;
+    #[account(
+        mut,
+        seeds = [b"treasury", dao.key().as_ref()],
+        bump = dao.treasury_bump
+    )]
+    pub treasury: SystemAccount<'info>,
 
-    // Convert public inputs to fixed-size array format
-    let mut public_inputs_arr: [[u8; 32]; PUBLIC_INPUT_COUNT] = [[0u8; 32]; PUBLIC_INPUT_COUNT];
-    public_inputs_arr[0].copy_from_slice(voters_root);
-    public_inputs_arr[1].copy_from_slice(nullifier);
-    public_inputs_arr[2].copy_from_slice(&proposal_id_bytes);
-    public_inputs_arr[3].copy_from_slice(&vote_bytes);
-    public_inputs_arr[4].copy_from_slice(&num_options_bytes);
+    #[account(
+        mut,
+        has_one = proposal
+    )]
+    pub action: Account<'info, ProposalAction>,
 
-    // Construct verifying key
-    let vk = Groth16Verifyingkey {
-        nr_pubinputs: PUBLIC_INPUT_COUNT,
-        vk_alpha_g1: verifying_key::ALPHA,
-        vk_beta_g2: verifying_key::BETA,
-        vk_gamme_g2: verifying_key::GAMMA,
-        vk_delta_g2: verifying_key::DELTA,
-        vk_ic: &verifying_key::IC,
-    };
+    /// Required when the proposal has a prerequisite
+    pub prerequisite: Option<Account<'info, Proposal>>,
 
-    // Groth16 on-chain verification using Solana's altbn254 precompiles
-    let mut verifier = Groth16Verifier::new(
-        &proof_a,
-        &proof_b,
-        &proof_c,
-        &public_inputs_arr,
-        &vk,
-    )
-    .map_err(|_| VotingError::InvalidProof)?;
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+        constraint = !config.paused @ VotingError::ProgramPaused
+    )]
+    pub config: Account<'info, ProgramConfig>,
 
-    verifier.verify().map_err(|_| VotingError::InvalidProof)?;
+    pub token_program: Program<'info, Token>,
 
-    Ok(())
+    pub system_program: Program<'info, System>,
 }
 
 #[derive(Accounts)]
-#[instruction(proposal_id: u64)]
-pub struct CreateProposal<'info> {
+pub struct ProposeAuthorityTransfer<'info> {
     #[account(
-        init,
-        payer = authority,
-        space = 8 + Proposal::INIT_SPACE,
-        seeds = [b"proposal", proposal_id.to_le_bytes().as_ref()],
-        bump
+        mut,
+        has_one = authority
     )]
     pub proposal: Account<'info, Proposal>,
 
-    #[account(mut)]
     pub authority: Signer<'info>,
-
-    pub system_program: Program<'info, System>,
 }
 
 #[derive(Accounts)]
-#[instruction(nullifier: [u8; 32])]
-pub struct CastVote<'info> {
-    #[account(mut)]
+pub struct AcceptAuthorityTransfer<'info> {
+    #[account(
+        mut,
+        constraint = proposal.pending_authority == Some(new_authority.key())
+            @ VotingError::NotPendingAuthority
+    )]
     pub proposal: Account<'info, Proposal>,
 
+    pub new_authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct ProposeDaoAuthorityTransfer<'info> {
     #[account(
-        init,
-        payer = payer,
-        space = 8 + NullifierAccount::INIT_SPACE,
-        seeds = [b"nullifier", proposal.key().as_ref(), nullifier.as_ref()],
-        bump
+        mut,
+        has_one = authority @ VotingError::Unauthorized
     )]
-    pub nullifier_account: Account<'info, NullifierAccount>,
+    pub dao: Account<'info, Dao>,
 
-    #[account(mut)]
-    pub payer: Signer<'info>,
+    pub authority: Signer<'info>,
+}
 
-    pub system_program: Program<'info, System>,
+#[derive(Accounts)]
+pub struct AcceptDaoAuthorityTransfer<'info> {
+    #[account(
+        mut,
+        constraint = dao.pending_authority == Some(new_authority.key())
+            @ VotingError::NotPendingAuthority
+    )]
+    pub dao: Account<'info, Dao>,
+
+    pub new_authority: Signer<'info>,
 }
 
 #[derive(Accounts)]
-pub struct FinalizeProposal<'info> {
+pub struct ProposeRegistryAuthorityTransfer<'info> {
     #[account(
         mut,
-        has_one = authority
+        has_one = authority @ VotingError::Unauthorized
     )]
-    pub proposal: Account<'info, Proposal>,
+    pub registry: Box<Account<'info, VoterRegistry>>,
 
     pub authority: Signer<'info>,
 }
 
+#[derive(Accounts)]
+pub struct AcceptRegistryAuthorityTransfer<'info> {
+    #[account(
+        mut,
+        constraint = registry.pending_authority == Some(new_authority.key())
+            @ VotingError::NotPendingAuthority
+    )]
+    pub registry: Box<Account<'info, VoterRegistry>>,
+
+    pub new_authority: Signer<'info>,
+}
+
+/// Program-wide settings (singleton PDA, seeds: ["config"])
+#[account]
+#[derive(InitSpace)]
+pub struct ProgramConfig {
+    /// Key allowed to pause and unpause the program
+    pub guardian: Pubkey,
+    /// Nominee of an in-progress two-step guardian transfer
+    pub pending_guardian: Option<Pubkey>,
+    /// When set, voting, proposal creation and treasury actions are rejected
+    pub paused: bool,
+    pub bump: u8,
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct Dao {
+    pub dao_id: u64,
+    pub authority: Pubkey,
+    #[max_len(64)]
+    pub name: String,
+    /// Minimum proposal turnout, in basis points, for actions to execute
+    pub quorum_bps: u16,
+    /// Nominee of an in-progress two-step authority transfer
+    pub pending_authority: Option<Pubkey>,
+    /// Bump of the treasury vault PDA (seeds: ["treasury", dao])
+    pub treasury_bump: u8,
+    pub bump: u8,
+}
+
 #[account]
 #[derive(InitSpace)]
 pub struct Proposal {
@@ -459,13 +4562,605 @@ pub struct Proposal {
     pub description: String,
     /// Number of vote options (2-8)
     pub num_options: u8,
-    /// Vote counts for each option (index 0 to num_options-1)
+    /// Vote counts for each option (index 0 to num_options-1); point totals on
+    /// points proposals
     pub vote_counts: [u64; 8],
     /// Labels for each option (e.g., ["Yes", "No"] or ["A", "B", "C", "D"])
     #[max_len(8, 32)]
     pub option_labels: Vec<String>,
     pub voting_ends_at: i64,
     pub is_finalized: bool,
+    /// Result recorded by finalize_proposal
+    pub outcome: ProposalOutcome,
+    /// Winning option index (only meaningful when outcome is Succeeded)
+    pub winning_option: u8,
+    /// Option whose win means the proposal passed, for dependent proposals
+    pub approval_option: Option<u8>,
+    /// Number of leaves in voters_root, fixed at creation
+    pub eligible_voters: u64,
+    /// Ballots cast so far (one per nullifier, regardless of weight)
+    pub total_votes: u64,
+    /// Sealed ballots not revealed (CommitReveal and HiddenTally proposals);
+    /// after the reveal window these are reported separately from vote_counts
+    pub unrevealed_votes: u64,
+    /// Ballots counted in the outcome (total_votes - unrevealed_votes) per
+    /// eligible voter in basis points, recorded at finalization
+    pub turnout_bps: u16,
+    /// DAO whose treasury this proposal governs, if any
+    pub dao: Option<Pubkey>,
+    /// Number of treasury actions attached to this proposal
+    pub action_count: u8,
+    /// Proposal that must succeed before this one can be voted on or executed
+    pub prerequisite: Option<Pubkey>,
+    /// Nominee of an in-progress two-step authority transfer
+    pub pending_authority: Option<Pubkey>,
+    /// Voter registry whose root was snapshotted as voters_root, if any
+    pub registry: Option<Pubkey>,
+    /// Registry leaf count at the snapshot; later roots are rejected
+    pub snapshot_leaf_count: u64,
+    /// Accept proofs against recent registry roots from before the snapshot
+    pub accept_recent_roots: bool,
+    /// Token snapshot the voters were drawn from, for snapshot registries
+    pub token_snapshot: Option<TokenSnapshot>,
+    /// Registry revocation root at creation; ballots prove their leaf is not in it
+    pub revocation_root: [u8; 32],
+    /// Ballots carry the token weight committed in the voter's leaf
+    pub weighted: bool,
+    /// How the ballot's `vote` value is interpreted
+    pub ballot_mode: BallotMode,
+    /// Voters can replace their ballot until voting ends (revise_vote)
+    pub allow_revision: bool,
+    /// Instant-runoff progress and round counts (Ranked proposals only)
+    pub irv: IrvTabulation,
+    /// Committee that generated the tally key (Encrypted proposals only);
+    /// the tally can then only be decrypted from its members' shares
+    pub committee: Option<Pubkey>,
+    /// Weighted voter sets; when present, vote_counts is only filled in at
+    /// finalization from the groups' tallies
+    #[max_len(MAX_VOTER_GROUPS)]
+    pub voter_groups: Vec<VoterGroup>,
+    pub bump: u8,
+}
+
+impl Proposal {
+    /// Whether the proposal was finalized with its approval option winning
+    fn passed(&self) -> bool {
+        self.outcome == ProposalOutcome::Succeeded
+            && self.approval_option == Some(self.winning_option)
+    }
+}
+
+/// Optional proposal settings passed to create_proposal
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct ProposalParams {
+    /// Option whose win counts as approving the proposal (e.g. "Yes");
+    /// required for proposals used as another's prerequisite
+    pub approval_option: Option<u8>,
+    /// Registry proposals only: allow creating the proposal while the
+    /// registry is still Open, and accept proofs against any root still in the
+    /// registry's history as long as it predates this proposal's snapshot.
+    /// Members registered after the snapshot can't vote on the proposal
+    pub accept_recent_roots: bool,
+    /// Number of leaves in voters_root, attested by the authority; required
+    /// without a registry. Registry proposals copy the registry's active
+    /// member count, and a supplied value must match it
+    pub eligible_voters: Option<u64>,
+    /// Up to MAX_VOTER_GROUPS weighted voter sets (e.g. a council and a
+    /// community house), each tallied separately with its own nullifier
+    /// domain and combined by weight at finalization. Group 0's root is the
+    /// proposal's voters_root and `eligible_voters` (if given) must equal the
+    /// groups' total. Leave empty for a single unweighted voter set; groups
+    /// can't be used with a registry
+    pub voter_groups: Vec<VoterGroupConfig>,
+    /// Leaves of voters_root are hash(commitment, weight) and each ballot
+    /// counts with the weight proven in-circuit instead of 1. Registry
+    /// proposals must match the registry's `weighted` flag
+    pub weighted: bool,
+    /// `Single` for one option per ballot, `Approval` for a bitmask approving
+    /// any non-empty subset of the options, `Ranked` for instant-runoff
+    /// ballots (cast_ranked_vote, tabulate_irv) without voter groups, and
+    /// `Quadratic` for vote vectors (cast_vector_vote) paid from the credit
+    /// budget committed in each leaf, which requires `weighted`.
+    /// `Points { budget }` ballots split exactly `budget` points over the
+    /// options (cast_vector_vote). `Encrypted { key }` ballots are
+    /// ElGamal-encrypted under `key` (open_encrypted_ballot,
+    /// post_encrypted_options, cast_encrypted_vote) and only the totals are
+    /// revealed, by decrypt_tally; they can't be weighted or have voter groups.
+    /// An all-zero `key` is generated later by a decryption committee
+    /// (set_decryption_committee) and voting can't start until it is.
+    /// Their running tally lives in an EncryptedTally account (seeds:
+    /// ["encrypted_tally", proposal]) passed as `encrypted_tally`, which is
+    /// required for encrypted proposals and rejected otherwise.
+    /// `CommitReveal { reveal_ends_at }` ballots commit to a single choice
+    /// while voting is open (cast_sealed_vote) and are opened with
+    /// reveal_vote between voting_ends_at and `reveal_ends_at`.
+    /// `HiddenTally { reveal_ends_at, tally_key }` ballots are sealed the
+    /// same way but also escrow their opening encrypted to `tally_key`, so
+    /// vote_counts stays zero until the key holder runs reveal_tally
+    pub ballot_mode: BallotMode,
+    /// Voters can replace their ballot with revise_vote until voting ends;
+    /// only the latest one counts. Single, approval, quadratic and points
+    /// proposals only
+    pub allow_revision: bool,
+}
+
+/// One weighted voter set of a multi-group proposal
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, InitSpace)]
+pub struct VoterGroup {
+    pub voters_root: [u8; 32],
+    /// Multiplier applied to this group's tally at finalization
+    pub weight: u32,
+    /// Number of leaves in voters_root, attested by the proposal authority
+    pub eligible_voters: u64,
+    /// Votes cast by this group for each option (token-weighted on weighted
+    /// proposals)
+    pub vote_counts: [u64; 8],
+}
+
+/// Voter group definition passed to create_proposal
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct VoterGroupConfig {
+    pub voters_root: [u8; 32],
+    pub weight: u32,
+    pub eligible_voters: u64,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, InitSpace)]
+pub struct TokenSnapshot {
+    pub mint: Pubkey,
+    /// Slot at which holder balances were read
+    pub slot: u64,
+}
+
+/// Ballot encoding of a proposal
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Debug, InitSpace)]
+pub enum BallotMode {
+    /// `vote` is a single option index
+    Single,
+    /// `vote` is a bitmask of approved options; every set bit gets the
+    /// ballot's weight
+    Approval,
+    /// Ballots rank the options and are tallied by instant runoff
+    Ranked,
+    /// Ballots carry a vote vector costing sum(votes^2) credits, paid from
+    /// the hidden budget committed in the voter's weighted leaf
+    Quadratic,
+    /// Ballots split exactly `budget` points across the options; tallies are
+    /// point totals
+    Points { budget: u32 },
+    /// Ballots are per-option exponential-ElGamal ciphertexts under `key` (a
+    /// G1 point) encrypting a one-hot vector; only the totals are decrypted
+    Encrypted { key: [u8; 64] },
+    /// Ballots are commitments to a single choice, opened after voting ends
+    /// and until `reveal_ends_at`
+    CommitReveal { reveal_ends_at: i64 },
+    /// Sealed ballots like CommitReveal that also carry their opening
+    /// encrypted to `tally_key` (an X25519 key of a decryption committee or
+    /// time-lock service, only used off-chain), so the key holder reveals
+    /// the whole tally after voting ends. The escrowed openings aren't
+    /// verified; anyone holding an opening can reveal its ballot
+    HiddenTally {
+        reveal_ends_at: i64,
+        tally_key: [u8; 32],
+    },
+}
+
+impl BallotMode {
+    /// Public input telling the circuit how to constrain `vote`
+    fn code(&self) -> u8 {
+        match self {
+            BallotMode::Single => 0,
+            BallotMode::Approval => 1,
+            BallotMode::Ranked => 2,
+            BallotMode::Quadratic => 3,
+            BallotMode::Points { .. } => 4,
+            BallotMode::Encrypted { .. } => 5,
+            BallotMode::CommitReveal { .. } | BallotMode::HiddenTally { .. } => 6,
+        }
+    }
+
+    /// Point budget public input (0 outside points mode)
+    fn point_budget(&self) -> u32 {
+        match self {
+            BallotMode::Points { budget } => *budget,
+            _ => 0,
+        }
+    }
+
+    /// End of the reveal window of sealed-ballot modes
+    fn reveal_ends_at(&self) -> Option<i64> {
+        match self {
+            BallotMode::CommitReveal { reveal_ends_at }
+            | BallotMode::HiddenTally { reveal_ends_at, .. } => Some(*reveal_ends_at),
+            _ => None,
+        }
+    }
+
+    /// Whether ballots are encrypted and tallied homomorphically
+    fn is_encrypted(&self) -> bool {
+        matches!(self, BallotMode::Encrypted { .. })
+    }
+
+    /// Whether ballots are per-option vote vectors (cast_vector_vote)
+    fn is_vector(&self) -> bool {
+        matches!(self, BallotMode::Quadratic | BallotMode::Points { .. })
+    }
+
+    /// Validate a ballot's `vote` and return the options it counts toward as
+    /// a bitmask (bit i = option i)
+    fn selected_options(&self, vote: u8, num_options: u8) -> Result<u8> {
+        match self {
+            BallotMode::Single => {
+                require!(vote < num_options, VotingError::InvalidVote);
+                Ok(1 << vote)
+            }
+            BallotMode::Approval => {
+                require!(
+                    vote != 0 && (vote as u16) >> num_options == 0,
+                    VotingError::InvalidApprovalMask
+                );
+                Ok(vote)
+            }
+            BallotMode::Ranked => err!(VotingError::RankedBallotRequired),
+            BallotMode::Quadratic | BallotMode::Points { .. } => {
+                err!(VotingError::VectorBallotRequired)
+            }
+            BallotMode::Encrypted { .. } => err!(VotingError::EncryptedBallotRequired),
+            BallotMode::CommitReveal { .. } | BallotMode::HiddenTally { .. } => {
+                err!(VotingError::SealedBallotRequired)
+            }
+        }
+    }
+}
+
+/// Instant-runoff tabulation state, advanced by tabulate_irv
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Default, InitSpace)]
+pub struct IrvTabulation {
+    /// Current elimination round (0 = first preferences)
+    pub round: u8,
+    /// Bitmask of eliminated options
+    pub eliminated: u8,
+    /// Ballots counted so far in the current round
+    pub ballots_counted: u64,
+    /// Counts of the current round, accumulated across transactions
+    pub current: [u64; 8],
+    /// Counts of every closed round, in order
+    #[max_len(8)]
+    pub rounds: Vec<[u64; 8]>,
+    /// Set once a winner (or a final tie) has been determined
+    pub complete: bool,
+}
+
+/// Homomorphic tally of an encrypted proposal
+/// (seeds: ["encrypted_tally", proposal])
+#[account]
+#[derive(InitSpace)]
+pub struct EncryptedTally {
+    pub proposal: Pubkey,
+    /// Per-option sum of the ballots' ciphertexts (c1 || c2)
+    pub ciphertexts: [[u8; 128]; 8],
+    /// Set once decrypt_tally has written the totals into vote_counts
+    pub decrypted: bool,
+    pub bump: u8,
+}
+
+/// One option of an encrypted ballot, as posted by post_encrypted_options
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct EncryptedOption {
+    /// c1 || c2 = r*G || m*G + r*key
+    pub ciphertext: [u8; 128],
+    pub proof: BitProof,
+}
+
+/// Disjunctive Chaum-Pedersen proof that a ciphertext encrypts 0 or 1, one
+/// challenge and response per branch (scalars, big-endian)
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct BitProof {
+    pub challenges: [[u8; 32]; 2],
+    pub responses: [[u8; 32]; 2],
+}
+
+/// Opening of a sealed ballot's commitment Poseidon(vote, salt)
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct VoteOpening {
+    pub vote: u8,
+    pub salt: [u8; 32],
+}
+
+/// Chaum-Pedersen proof of equal discrete logs (scalars, big-endian)
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct DleqProof {
+    pub challenge: [u8; 32],
+    pub response: [u8; 32],
+}
+
+/// A committee member's share x_j*c1 of one option's tally, with a proof
+/// that x_j matches the member's key
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct PartialDecryption {
+    pub share: [u8; 64],
+    pub proof: DleqProof,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, InitSpace)]
+pub enum ProposalOutcome {
+    /// Voting still open or not yet finalized
+    Pending,
+    /// A single option received the most votes
+    Succeeded,
+    /// Two or more options share the highest count
+    Tied,
+    /// No votes were cast
+    NoVotes,
+}
+
+/// A treasury action attached to one option of a DAO proposal
+#[account]
+#[derive(InitSpace)]
+pub struct ProposalAction {
+    pub proposal: Pubkey,
+    /// Position of this action within the proposal (seeds: ["action", proposal, index])
+    pub index: u8,
+    /// Option that must win for the action to run
+    pub option: u8,
+    pub action: Action,
+    pub executed: bool,
+    pub bump: u8,
+}
+
+/// Built-in actions the DAO treasury can perform
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, InitSpace)]
+pub enum Action {
+    /// Send lamports from the treasury vault
+    TransferSol { recipient: Pubkey, lamports: u64 },
+    /// Move tokens out of a treasury-owned token account
+    TransferToken {
+        source: Pubkey,
+        destination: Pubkey,
+        amount: u64,
+    },
+    /// Close a treasury-owned token account and reclaim its rent
+    CloseAccount { account: Pubkey, destination: Pubkey },
+    /// Upgrade `program` from `buffer` through the BPF upgradeable loader.
+    /// The DAO PDA must already be the upgrade authority of both the program
+    /// and the buffer; the buffer's lamports are refunded to `spill`.
+    UpgradeProgram {
+        program: Pubkey,
+        buffer: Pubkey,
+        spill: Pubkey,
+    },
+}
+
+impl Action {
+    fn validate(&self) -> Result<()> {
+        match self {
+            Action::TransferSol { lamports, .. } => {
+                require!(*lamports > 0, VotingError::InvalidActionAmount)
+            }
+            Action::TransferToken { amount, .. } => {
+                require!(*amount > 0, VotingError::InvalidActionAmount)
+            }
+            Action::CloseAccount { .. } | Action::UpgradeProgram { .. } => {}
+        }
+        Ok(())
+    }
+}
+
+/// On-chain incremental Merkle tree of voter commitments
+#[account]
+#[derive(InitSpace)]
+pub struct VoterRegistry {
+    pub registry_id: u64,
+    /// Update authority: manages the registry (and inserts leaves under
+    /// Eligibility::Authority)
+    pub authority: Pubkey,
+    /// Nominee of an in-progress two-step authority transfer
+    pub pending_authority: Option<Pubkey>,
+    /// Who may add leaves to the tree
+    pub eligibility: Eligibility,
+    /// Lifecycle stage; leaves are only accepted while Open
+    pub status: RegistryStatus,
+    /// Leaves commit to a voting weight, hash(commitment, weight)
+    pub weighted: bool,
+    /// Number of leaves inserted so far (index of the next leaf)
+    pub next_index: u64,
+    /// Active members: leaves inserted minus leaves revoked
+    pub member_count: u64,
+    /// Slot of the last insertion or revocation
+    pub last_update_slot: u64,
+    /// Rightmost filled node at each level, enough to append in O(depth)
+    pub filled_subtrees: [[u8; 32]; TREE_DEPTH],
+    /// Current Merkle root (big-endian field element)
+    pub root: [u8; 32],
+    /// Ring buffer of the most recent roots, including the current one
+    pub root_history: [HistoricalRoot; ROOT_HISTORY_SIZE],
+    /// Position of the current root in root_history
+    pub root_history_index: u8,
+    /// Root of the revocation tree; slot i is non-zero once leaf i is revoked
+    pub revocation_root: [u8; 32],
+    /// Number of leaves revoked so far
+    pub revoked_count: u64,
+    pub bump: u8,
+}
+
+/// Lifecycle of a voter registry
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Debug, InitSpace)]
+pub enum RegistryStatus {
+    /// Commitments can be added
+    Open,
+    /// Root is fixed; proposals can be created against it
+    Frozen,
+    /// Retired; no new proposals or revocations
+    Archived,
+}
+
+/// Rule deciding who can add a leaf to a voter registry
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, InitSpace)]
+pub enum Eligibility {
+    /// The registry authority inserts commitments directly
+    Authority,
+    /// Wallets with an AllowlistEntry register their own commitment
+    Allowlist,
+    /// Wallets that held at least `min_amount` of `mint` at `slot` register
+    /// their own commitment until `registration_ends_at`, proving their
+    /// balance against `holders_root` (built by tools/token-snapshot)
+    TokenSnapshot {
+        mint: Pubkey,
+        slot: u64,
+        min_amount: u64,
+        holders_root: [u8; 32],
+        registration_ends_at: i64,
+    },
+    /// One commitment per NFT carrying verified Metaplex collection metadata
+    /// for `collection` (the collection NFT's mint)
+    NftCollection { collection: Pubkey },
+}
+
+/// Proof that a wallet is in a token snapshot with the given balance
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct HolderProof {
+    /// Wallet's total balance of the mint at the snapshot slot
+    pub balance: u64,
+    /// Position of the wallet's leaf in the snapshot tree
+    pub index: u32,
+    /// Sibling hashes from the leaf up to the root
+    pub siblings: Vec<[u8; 32]>,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Default, InitSpace)]
+pub struct HistoricalRoot {
+    pub root: [u8; 32],
+    /// Number of leaves in the tree when this root was produced
+    pub leaf_count: u64,
+    /// Slot in which this root was produced
+    pub slot: u64,
+}
+
+/// Marks a wallet as eligible for an allowlist registry
+/// (seeds: ["allowlist", registry, wallet])
+#[account]
+#[derive(InitSpace)]
+pub struct AllowlistEntry {
+    pub registry: Pubkey,
+    pub wallet: Pubkey,
+    pub bump: u8,
+}
+
+/// Commitment a wallet contributed to a registry
+/// (seeds: ["commitment", registry, wallet])
+#[account]
+#[derive(InitSpace)]
+pub struct VoterCommitment {
+    pub registry: Pubkey,
+    pub wallet: Pubkey,
+    /// Voter commitment, hash(secret, secret)
+    pub commitment: [u8; 32],
+    /// Inserted leaf: the commitment, or hash(commitment, weight) on weighted
+    /// registries (the weight itself is not stored)
+    pub leaf: [u8; 32],
+    /// Position of the leaf in the tree
+    pub leaf_index: u64,
+    pub bump: u8,
+}
+
+/// Commitment an NFT contributed to a registry; its existence marks the NFT
+/// as used (seeds: ["nft", registry, mint])
+#[account]
+#[derive(InitSpace)]
+pub struct NftCommitment {
+    pub registry: Pubkey,
+    pub mint: Pubkey,
+    /// Wallet that held the NFT when it registered
+    pub wallet: Pubkey,
+    /// Leaf inserted into the tree, hash(secret, secret)
+    pub commitment: [u8; 32],
+    /// Position of the leaf in the tree
+    pub leaf_index: u64,
+    pub bump: u8,
+}
+
+/// A ranked ballot awaiting tabulation (seeds: ["ballot", proposal, nullifier])
+#[account]
+#[derive(InitSpace)]
+pub struct RankedBallot {
+    pub proposal: Pubkey,
+    /// Options in preference order, one per nibble from the lowest, ending
+    /// with 0xF
+    pub ranking: u32,
+    /// Votes the ballot carries to whichever option it counts toward
+    pub weight: u64,
+    /// Number of IRV rounds the ballot has been counted in
+    pub rounds_counted: u8,
+    pub bump: u8,
+}
+
+/// A committed ballot of a CommitReveal or HiddenTally proposal
+/// (seeds: ["sealed_ballot", proposal, group, nullifier])
+#[account]
+#[derive(InitSpace)]
+pub struct SealedBallot {
+    pub proposal: Pubkey,
+    /// Position of the ballot in the proposal's ballot log (cast order)
+    pub index: u64,
+    /// Poseidon(vote, salt)
+    pub commitment: [u8; 32],
+    /// Voter group whose tally the revealed vote counts toward
+    pub group: u8,
+    /// Votes the ballot adds to its option once revealed
+    pub weight: u64,
+    /// (vote, salt) encrypted to the tally key (HiddenTally proposals only)
+    #[max_len(MAX_ENCRYPTED_OPENING_SIZE)]
+    pub encrypted_opening: Vec<u8>,
+    pub revealed: bool,
+    pub bump: u8,
+}
+
+/// Threshold committee generating and decrypting an encrypted proposal's
+/// tally key (seeds: ["committee", proposal])
+#[account]
+#[derive(InitSpace)]
+pub struct DecryptionCommittee {
+    pub proposal: Pubkey,
+    /// Number of members needed to decrypt
+    pub threshold: u8,
+    /// Wallet of each member, which signs the member's dealing
+    #[max_len(MAX_COMMITTEE_SIZE)]
+    pub members: Vec<Pubkey>,
+    /// Key x_j*G of each member's share of the tally key, summed over the
+    /// dealings submitted so far
+    #[max_len(MAX_COMMITTEE_SIZE)]
+    pub share_keys: Vec<[u8; 64]>,
+    /// Sum of the qualified dealings' a_0*G; the proposal's key once fixed
+    pub tally_key: [u8; 64],
+    /// Bitmask of the members whose dealing has been submitted
+    pub dealt: u8,
+    /// Digest of each member's dealing commitments
+    pub dealing_hashes: [[u8; 32]; MAX_COMMITTEE_SIZE],
+    /// Per dealer, bitmask of the members with an unanswered complaint
+    pub complaints: [u8; MAX_COMMITTEE_SIZE],
+    /// Bitmask of the dealers disqualified for an unanswered complaint
+    pub disqualified: u8,
+    pub dealings_end_at: i64,
+    pub complaints_end_at: i64,
+    pub answers_end_at: i64,
+    /// Whether fix_committee_key has set the proposal's key
+    pub key_fixed: bool,
+    /// Partial decryptions x_j*c1 of each option's tally, per member
+    pub shares: [[[u8; 64]; 8]; MAX_COMMITTEE_SIZE],
+    /// Per member, bitmask of the options whose share has been submitted
+    pub submitted: [u8; MAX_COMMITTEE_SIZE],
+    pub bump: u8,
+}
+
+/// An encrypted ballot being uploaded
+/// (seeds: ["encrypted_ballot", proposal, nullifier, payer])
+#[account]
+#[derive(InitSpace)]
+pub struct EncryptedBallot {
+    /// Per-option ciphertexts, each proven to encrypt 0 or 1
+    pub ciphertexts: [[u8; 128]; 8],
+    /// Bitmask of the options posted so far
+    pub posted: u8,
     pub bump: u8,
 }
 
@@ -474,9 +5169,88 @@ pub struct Proposal {
 pub struct NullifierAccount {
     pub nullifier: [u8; 32],
     pub proposal: Pubkey,
+    /// Voter group the nullifier was spent in (seeds: ["nullifier", proposal, group, nullifier])
+    pub group: u8,
+    /// Votes the ballot added to each option, subtracted again if the ballot
+    /// is revised (zero for ballots tallied later: ranked, encrypted, sealed)
+    pub counts: [u64; 8],
+    /// Number of times the ballot has been replaced with revise_vote
+    pub revisions: u16,
     pub bump: u8,
 }
 
+#[event]
+pub struct ProgramPaused {
+    pub guardian: Pubkey,
+}
+
+#[event]
+pub struct ProgramUnpaused {
+    pub guardian: Pubkey,
+}
+
+#[event]
+pub struct RegistryCreated {
+    pub registry: Pubkey,
+    pub registry_id: u64,
+    pub authority: Pubkey,
+    pub eligibility: Eligibility,
+    pub weighted: bool,
+}
+
+#[event]
+pub struct AllowlistEntryAdded {
+    pub registry: Pubkey,
+    pub wallet: Pubkey,
+}
+
+#[event]
+pub struct CommitmentRegistered {
+    pub registry: Pubkey,
+    pub leaf_index: u64,
+    pub commitment: [u8; 32],
+    /// Registry root after the insertion
+    pub root: [u8; 32],
+    pub member_count: u64,
+    pub slot: u64,
+}
+
+#[event]
+pub struct CommitmentRevoked {
+    pub registry: Pubkey,
+    pub leaf_index: u64,
+    /// Revocation root after the revocation
+    pub revocation_root: [u8; 32],
+    pub member_count: u64,
+    pub slot: u64,
+}
+
+#[event]
+pub struct RegistryStatusChanged {
+    pub registry: Pubkey,
+    pub status: RegistryStatus,
+    /// Registry root at the transition
+    pub root: [u8; 32],
+    pub member_count: u64,
+    pub slot: u64,
+}
+
+#[event]
+pub struct AuthorityTransferProposed {
+    /// Account whose authority is changing
+    pub account: Pubkey,
+    pub authority: Pubkey,
+    pub pending_authority: Pubkey,
+}
+
+#[event]
+pub struct AuthorityTransferAccepted {
+    /// Account whose authority changed
+    pub account: Pubkey,
+    pub previous_authority: Pubkey,
+    pub new_authority: Pubkey,
+}
+
 #[error_code]
 pub enum VotingError {
     #[msg("Voting period has ended")]
@@ -497,4 +5271,228 @@ pub enum VotingError {
     TooManyOptions,
     #[msg("Number of option labels must match num_options")]
     OptionLabelsMismatch,
+    #[msg("Signer is not authorized for this account")]
+    Unauthorized,
+    #[msg("Proposal is not linked to a DAO")]
+    ProposalHasNoDao,
+    #[msg("Proposal does not belong to this DAO")]
+    DaoMismatch,
+    #[msg("Actions cannot be added after voting has started")]
+    VotingStarted,
+    #[msg("Action option must be less than num_options")]
+    InvalidActionOption,
+    #[msg("Too many actions on this proposal")]
+    TooManyActions,
+    #[msg("Action amount must be greater than zero")]
+    InvalidActionAmount,
+    #[msg("Proposal has not been finalized yet")]
+    ProposalNotFinalized,
+    #[msg("Action is not attached to the winning option")]
+    ActionNotApproved,
+    #[msg("Action has already been executed")]
+    ActionAlreadyExecuted,
+    #[msg("An account referenced by the action was not provided")]
+    ActionAccountMissing,
+    #[msg("Treasury actions are disabled while proof verification is off")]
+    VerificationDisabled,
+    #[msg("Prerequisite proposal account missing or does not match")]
+    PrerequisiteMissing,
+    #[msg("Prerequisite proposal has not been finalized yet")]
+    PrerequisiteNotFinalized,
+    #[msg("Prerequisite proposal did not pass with its approval option")]
+    PrerequisiteNotPassed,
+    #[msg("Prerequisite proposal has no approval option")]
+    PrerequisiteWithoutApprovalOption,
+    #[msg("Approval option out of range")]
+    InvalidApprovalOption,
+    #[msg("Signer is not the pending authority")]
+    NotPendingAuthority,
+    #[msg("Program is paused by the guardian")]
+    ProgramPaused,
+    #[msg("Program is not paused")]
+    ProgramNotPaused,
+    #[msg("Commitment must be a non-zero BN254 field element")]
+    InvalidCommitment,
+    #[msg("Voter registry tree is full")]
+    RegistryFull,
+    #[msg("voters_root does not match the registry's current root")]
+    RegistryRootMismatch,
+    #[msg("A voter registry account is required")]
+    RegistryRequired,
+    #[msg("Registry does not match the proposal's registry")]
+    RegistryMismatch,
+    #[msg("Proposal only accepts proofs against its own voters_root")]
+    RootNotAccepted,
+    #[msg("Root is not in the registry's recent root history")]
+    RootNotInHistory,
+    #[msg("Root was produced after the proposal's snapshot")]
+    RootAfterSnapshot,
+    #[msg("Registry only accepts wallet-bound registrations")]
+    WalletRegistrationOnly,
+    #[msg("Registry does not accept wallet registrations")]
+    WalletRegistrationDisabled,
+    #[msg("Registry does not use an allowlist")]
+    NotAllowlistRegistry,
+    #[msg("Wallet is not eligible to register in this registry")]
+    NotEligible,
+    #[msg("Registration window has closed")]
+    RegistrationClosed,
+    #[msg("Registry only accepts NFT registrations")]
+    NftRegistrationOnly,
+    #[msg("Registry is not gated by an NFT collection")]
+    NotNftRegistry,
+    #[msg("No leaf has been registered at this index")]
+    LeafNotRegistered,
+    #[msg("Revocation path does not match the registry, or the leaf is already revoked")]
+    RevocationPathMismatch,
+    #[msg("Registry counter overflow")]
+    RegistryCountOverflow,
+    #[msg("Eligible voter count is required for proposals without a registry")]
+    EligibleVotersRequired,
+    #[msg("Eligible voter count does not match the registry's member count or the voter groups")]
+    EligibleVotersMismatch,
+    #[msg("Quorum must be at most 10000 basis points")]
+    InvalidQuorum,
+    #[msg("Proposal turnout is below the DAO's quorum")]
+    QuorumNotMet,
+    #[msg("Too many voter groups on this proposal")]
+    TooManyVoterGroups,
+    #[msg("Voter groups cannot be combined with a registry")]
+    VoterGroupsWithRegistry,
+    #[msg("voters_root must equal the first voter group's root")]
+    VoterGroupRootMismatch,
+    #[msg("Voter group weight must be greater than zero")]
+    InvalidGroupWeight,
+    #[msg("Voter group does not exist on this proposal")]
+    InvalidVoterGroup,
+    #[msg("Vote tally overflow")]
+    TallyOverflow,
+    #[msg("Registry is not open for registration")]
+    RegistryNotOpen,
+    #[msg("Registry must be frozen before proposals can use it without accept_recent_roots")]
+    RegistryNotFrozen,
+    #[msg("Registry has been archived")]
+    RegistryArchived,
+    #[msg("Registry is not in the required status for this transition")]
+    InvalidRegistryStatus,
+    #[msg("Weighted registries require authority or token-based eligibility")]
+    WeightNotSupported,
+    #[msg("Proposal weight mode does not match the registry")]
+    WeightModeMismatch,
+    #[msg("Weight must be non-zero on weighted proposals and zero otherwise")]
+    InvalidWeight,
+    #[msg("Approval ballot must select at least one option and only options below num_options")]
+    InvalidApprovalMask,
+    #[msg("Ranked proposals take ballots through cast_ranked_vote")]
+    RankedBallotRequired,
+    #[msg("Proposal does not use ranked ballots")]
+    NotRankedProposal,
+    #[msg("Ranking must list distinct options below num_options, at least one")]
+    InvalidRanking,
+    #[msg("Ranked proposals cannot have voter groups")]
+    VoterGroupsNotSupported,
+    #[msg("Ballot does not belong to this proposal")]
+    BallotMismatch,
+    #[msg("Ranked-choice tabulation is already complete")]
+    TabulationComplete,
+    #[msg("Ranked-choice tabulation has not completed")]
+    TabulationIncomplete,
+    #[msg("This proposal takes vote vectors through cast_vector_vote")]
+    VectorBallotRequired,
+    #[msg("Proposal does not use vote vector ballots")]
+    NotVectorProposal,
+    #[msg("Vote vector must cast at least one vote and only for options below num_options")]
+    InvalidVoteVector,
+    #[msg("Quadratic voting needs weighted leaves carrying each voter's credit budget")]
+    CreditBudgetRequired,
+    #[msg("Point budget must be greater than zero")]
+    InvalidPointBudget,
+    #[msg("Points must add up to the proposal's point budget")]
+    PointBudgetMismatch,
+    #[msg("Encrypted ballots can't be weighted or split into voter groups")]
+    EncryptedBallotsNotSupported,
+    #[msg("Encryption key must be a BN254 G1 point, or all zeros for a committee key")]
+    InvalidEncryptionKey,
+    #[msg("Proposal does not use encrypted ballots")]
+    NotEncryptedProposal,
+    #[msg("Encrypted proposals take encrypted ballots")]
+    EncryptedBallotRequired,
+    #[msg("Not a valid BN254 G1 point")]
+    InvalidCurvePoint,
+    #[msg("Invalid encrypted ballot proof")]
+    InvalidBallotProof,
+    #[msg("Every option of the encrypted ballot must be posted before casting")]
+    EncryptedBallotIncomplete,
+    #[msg("Encrypted tally must be decrypted before finalizing")]
+    TallyNotDecrypted,
+    #[msg("An encrypted tally account is required for, and only for, encrypted proposals")]
+    EncryptedTallyMismatch,
+    #[msg("Encrypted tally has already been decrypted")]
+    TallyAlreadyDecrypted,
+    #[msg("Expected one total and one proof per option")]
+    InvalidTally,
+    #[msg("Invalid decryption proof")]
+    InvalidDecryptionProof,
+    #[msg("Decryption committee must have between 1 and 8 members")]
+    InvalidCommitteeSize,
+    #[msg("Threshold must be between 1 and the committee size")]
+    InvalidThreshold,
+    #[msg("The tally key was already set; committees generate their own")]
+    TallyKeyAlreadySet,
+    #[msg("The decryption committee has not finished generating the tally key")]
+    TallyKeyPending,
+    #[msg("Dealing must have one commitment per threshold and a valid proof of its key")]
+    InvalidDealing,
+    #[msg("This member has already submitted a dealing")]
+    DealingAlreadySubmitted,
+    #[msg("Proposals with a decryption committee are decrypted from its shares")]
+    CommitteeDecryptionRequired,
+    #[msg("Not a member of the decryption committee")]
+    InvalidCommitteeMember,
+    #[msg("Not enough members have submitted all their decryption shares")]
+    NotEnoughShares,
+    #[msg("Decryption committee members must be distinct")]
+    DuplicateCommitteeMember,
+    #[msg("Committee round duration must be positive")]
+    InvalidRoundDuration,
+    #[msg("The committee's dealing round has ended")]
+    DealingRoundEnded,
+    #[msg("The committee's complaint round has ended")]
+    ComplaintRoundEnded,
+    #[msg("The committee's complaint round is still open")]
+    ComplaintRoundOpen,
+    #[msg("Dealer has been disqualified")]
+    DealerDisqualified,
+    #[msg("No complaint is outstanding against this dealer")]
+    NoComplaint,
+    #[msg("Share doesn't match the dealer's commitments")]
+    InvalidComplaintAnswer,
+    #[msg("Complaints against dealers are still outstanding")]
+    ComplaintsOutstanding,
+    #[msg("No qualified dealing contributes to the tally key")]
+    NoQualifiedDealings,
+    #[msg("Reveal window must end after voting ends")]
+    InvalidRevealWindow,
+    #[msg("Proposal does not use sealed ballots")]
+    NotSealedProposal,
+    #[msg("Commit-reveal proposals take sealed ballots")]
+    SealedBallotRequired,
+    #[msg("Reveal window has closed")]
+    RevealWindowClosed,
+    #[msg("Reveal window is still open")]
+    RevealWindowOpen,
+    #[msg("Ballot has already been revealed")]
+    BallotAlreadyRevealed,
+    #[msg("Vote and salt don't match the ballot's commitment")]
+    RevealMismatch,
+    #[msg("Encrypted opening must be 1-128 bytes on hidden-tally proposals and empty otherwise")]
+    InvalidEncryptedOpening,
+    #[msg("Expected one opening per sealed ballot account")]
+    OpeningCountMismatch,
+    #[msg("Vote revision is only supported on single, approval, quadratic and points proposals")]
+    RevisionNotSupported,
+    #[msg("Proposal does not allow vote revision")]
+    RevisionNotAllowed,
+    #[msg("Revision must be the next one for this nullifier")]
+    StaleRevision,
 }
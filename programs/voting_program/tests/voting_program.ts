@@ -1,8 +1,28 @@
 import * as anchor from "@coral-xyz/anchor";
 import { Program, BN } from "@coral-xyz/anchor";
 import { VotingProgram } from "../target/types/voting_program";
-import { Keypair, PublicKey, SystemProgram } from "@solana/web3.js";
+import {
+  Keypair,
+  LAMPORTS_PER_SOL,
+  PublicKey,
+  SystemProgram,
+  SYSVAR_CLOCK_PUBKEY,
+  SYSVAR_RENT_PUBKEY,
+  Transaction,
+  TransactionInstruction,
+} from "@solana/web3.js";
 import { expect } from "chai";
+import { buildPoseidon } from "circomlibjs";
+import { createHash, randomBytes } from "crypto";
+import * as fs from "fs";
+import * as path from "path";
+
+const BPF_LOADER_UPGRADEABLE_ID = new PublicKey(
+  "BPFLoaderUpgradeab1e11111111111111111111111"
+);
+const TOKEN_PROGRAM_ID = new PublicKey(
+  "TokenkegQfeZyiNwAJbNbGKPFXCWuYvf9Ss623VQ5DA"
+);
 
 describe("voting_program", () => {
   const provider = anchor.AnchorProvider.env();
@@ -15,40 +35,466 @@ describe("voting_program", () => {
   const proposalId = new BN(1);
   const votersRoot = Buffer.alloc(32);
   votersRoot.fill(0xab); // Mock voters root
+  const mockProof = Buffer.alloc(256); // Verification is disabled on-chain
+
+  const [configPda] = PublicKey.findProgramAddressSync(
+    [Buffer.from("config")],
+    program.programId
+  );
 
   let proposalPda: PublicKey;
-  let proposalBump: number;
 
-  before(async () => {
-    // Derive proposal PDA
-    [proposalPda, proposalBump] = PublicKey.findProgramAddressSync(
-      [Buffer.from("proposal"), proposalId.toArrayLike(Buffer, "le", 8)],
+  // --------------------------------------------------------------------------
+  // Helpers
+  // --------------------------------------------------------------------------
+
+  function findProposalPda(id: BN): PublicKey {
+    return PublicKey.findProgramAddressSync(
+      [Buffer.from("proposal"), id.toArrayLike(Buffer, "le", 8)],
+      program.programId
+    )[0];
+  }
+
+  function findNullifierPda(
+    proposal: PublicKey,
+    nullifier: Buffer,
+    group = 0
+  ): PublicKey {
+    return PublicKey.findProgramAddressSync(
+      [
+        Buffer.from("nullifier"),
+        proposal.toBuffer(),
+        Buffer.from([group]),
+        nullifier,
+      ],
+      program.programId
+    )[0];
+  }
+
+  function filled(byte: number): Buffer {
+    return Buffer.alloc(32, byte);
+  }
+
+  function now(): number {
+    return Math.floor(Date.now() / 1000);
+  }
+
+  /** Current unix_timestamp of the cluster's Clock sysvar */
+  async function clusterTime(): Promise<number> {
+    const clock = await provider.connection.getAccountInfo(SYSVAR_CLOCK_PUBKEY);
+    return Number(clock.data.readBigInt64LE(32));
+  }
+
+  /** Wait until the cluster clock has passed `timestamp` */
+  async function waitUntil(timestamp: number): Promise<void> {
+    while ((await clusterTime()) <= timestamp) {
+      await new Promise((resolve) => setTimeout(resolve, 500));
+    }
+  }
+
+  async function expectError(promise: Promise<unknown>, code: string) {
+    try {
+      await promise;
+    } catch (err) {
+      expect(err.toString()).to.include(code);
+      return;
+    }
+    expect.fail(`Expected ${code}`);
+  }
+
+  // Poseidon over BN254, matching the program's sol_poseidon syscall
+  let poseidon: Awaited<ReturnType<typeof buildPoseidon>>;
+  let zeroHashes: bigint[];
+
+  function poseidonHash(...inputs: bigint[]): bigint {
+    return BigInt(poseidon.F.toString(poseidon(inputs)));
+  }
+
+  /** Big-endian 32-byte encoding of a field element */
+  function fieldBytes(value: bigint): number[] {
+    return Array.from(Buffer.from(value.toString(16).padStart(64, "0"), "hex"));
+  }
+
+  /** Root of a depth-20 tree holding `leaf` at `index` with the given path */
+  function merkleRoot(leaf: bigint, index: number, siblings: bigint[]): bigint {
+    return siblings.reduce(
+      (node, sibling, depth) =>
+        (index >> depth) & 1
+          ? poseidonHash(sibling, node)
+          : poseidonHash(node, sibling),
+      leaf
+    );
+  }
+
+  // BN254 G1 arithmetic matching the program's altbn254 helpers; points are
+  // affine (x, y) with null for the identity
+  type Point = [bigint, bigint] | null;
+  const FIELD_MODULUS =
+    0x30644e72e131a029b85045b68181585d97816a916871ca8d3c208c16d87cfd47n;
+  const SCALAR_ORDER =
+    0x30644e72e131a029b85045b68181585d2833e84879b9709143e1f593f0000001n;
+  const G: Point = [1n, 2n];
+
+  function modp(value: bigint): bigint {
+    return ((value % FIELD_MODULUS) + FIELD_MODULUS) % FIELD_MODULUS;
+  }
+
+  function invp(value: bigint): bigint {
+    let result = 1n;
+    let base = modp(value);
+    for (let exp = FIELD_MODULUS - 2n; exp > 0n; exp >>= 1n) {
+      if (exp & 1n) result = (result * base) % FIELD_MODULUS;
+      base = (base * base) % FIELD_MODULUS;
+    }
+    return result;
+  }
+
+  function g1Add(a: Point, b: Point): Point {
+    if (!a) return b;
+    if (!b) return a;
+    const [x1, y1] = a;
+    const [x2, y2] = b;
+    if (x1 === x2 && modp(y1 + y2) === 0n) return null;
+    const slope =
+      x1 === x2
+        ? modp(3n * x1 * x1 * invp(2n * y1))
+        : modp((y2 - y1) * invp(x2 - x1));
+    const x3 = modp(slope * slope - x1 - x2);
+    return [x3, modp(slope * (x1 - x3) - y1)];
+  }
+
+  function g1Mul(point: Point, k: bigint): Point {
+    let result: Point = null;
+    let addend = point;
+    let bits = ((k % SCALAR_ORDER) + SCALAR_ORDER) % SCALAR_ORDER;
+    for (; bits > 0n; bits >>= 1n) {
+      if (bits & 1n) result = g1Add(result, addend);
+      addend = g1Add(addend, addend);
+    }
+    return result;
+  }
+
+  function g1Sub(a: Point, b: Point): Point {
+    return g1Add(a, b ? [b[0], modp(-b[1])] : null);
+  }
+
+  /** altbn254 encoding x || y (big-endian), all zeros for the identity */
+  function pointBytes(point: Point): Buffer {
+    return Buffer.from([
+      ...fieldBytes(point ? point[0] : 0n),
+      ...fieldBytes(point ? point[1] : 0n),
+    ]);
+  }
+
+  function pointFromBytes(bytes: Buffer): Point {
+    const x = BigInt("0x" + bytes.subarray(0, 32).toString("hex"));
+    const y = BigInt("0x" + bytes.subarray(32, 64).toString("hex"));
+    return x === 0n && y === 0n ? null : [x, y];
+  }
+
+  function randomScalar(): bigint {
+    return BigInt("0x" + randomBytes(32).toString("hex")) % SCALAR_ORDER;
+  }
+
+  /** sha256 truncated to 253 bits, as the program's hash_to_scalar */
+  function hashToScalar(parts: Buffer[]): bigint {
+    const digest = createHash("sha256").update(Buffer.concat(parts)).digest();
+    digest[0] &= 0x1f;
+    return BigInt("0x" + digest.toString("hex"));
+  }
+
+  /** Chaum-Pedersen proof that log_g1(h1) == log_g2(h2) == secret */
+  function proveDleq(g1: Point, g2: Point, secret: bigint, context: Buffer[]) {
+    const k = randomScalar();
+    const transcript = [
+      g1,
+      g1Mul(g1, secret),
+      g2,
+      g1Mul(g2, secret),
+      g1Mul(g1, k),
+      g1Mul(g2, k),
+    ];
+    const challenge = hashToScalar([...context, ...transcript.map(pointBytes)]);
+    return {
+      challenge: fieldBytes(challenge),
+      response: fieldBytes((k + challenge * secret) % SCALAR_ORDER),
+    };
+  }
+
+  /**
+   * Exponential-ElGamal encryption of a bit under `key`, with the disjunctive
+   * proof that it is 0 or 1 (the other branch is simulated)
+   */
+  function encryptBit(key: Point, bit: number, context: Buffer[]) {
+    const r = randomScalar();
+    const c1 = g1Mul(G, r);
+    const c2 = g1Add(g1Mul(G, BigInt(bit)), g1Mul(key, r));
+    const ciphertext = Buffer.concat([pointBytes(c1), pointBytes(c2)]);
+
+    const k = randomScalar();
+    const simulatedChallenge = randomScalar();
+    const simulatedResponse = randomScalar();
+    const other = g1Sub(c2, g1Mul(G, BigInt(1 - bit)));
+    const commitments: Point[][] = [];
+    commitments[bit] = [g1Mul(G, k), g1Mul(key, k)];
+    commitments[1 - bit] = [
+      g1Sub(g1Mul(G, simulatedResponse), g1Mul(c1, simulatedChallenge)),
+      g1Sub(g1Mul(key, simulatedResponse), g1Mul(other, simulatedChallenge)),
+    ];
+    const challenge = hashToScalar([
+      ...context,
+      pointBytes(key),
+      ciphertext,
+      ...commitments.flat().map(pointBytes),
+    ]);
+    const challenges: bigint[] = [];
+    const responses: bigint[] = [];
+    challenges[1 - bit] = simulatedChallenge;
+    responses[1 - bit] = simulatedResponse;
+    challenges[bit] = (challenge - simulatedChallenge + SCALAR_ORDER) % SCALAR_ORDER;
+    responses[bit] = (k + challenges[bit] * r) % SCALAR_ORDER;
+    return {
+      r,
+      c1,
+      c2,
+      option: {
+        ciphertext: Array.from(ciphertext),
+        proof: {
+          challenges: challenges.map(fieldBytes),
+          responses: responses.map(fieldBytes),
+        },
+      },
+    };
+  }
+
+  function findRegistryPda(id: BN): PublicKey {
+    return PublicKey.findProgramAddressSync(
+      [Buffer.from("registry"), id.toArrayLike(Buffer, "le", 8)],
+      program.programId
+    )[0];
+  }
+
+  async function createRegistry(
+    id: BN,
+    eligibility: any = { authority: {} },
+    weighted = false
+  ): Promise<PublicKey> {
+    const pda = findRegistryPda(id);
+    await program.methods
+      .createVoterRegistry(id, eligibility, weighted)
+      .accountsPartial({
+        registry: pda,
+        authority: authority.publicKey,
+        config: configPda,
+        systemProgram: SystemProgram.programId,
+      })
+      .rpc();
+    return pda;
+  }
+
+  function registerCommitment(registry: PublicKey, commitment: bigint) {
+    return program.methods
+      .registerCommitment(fieldBytes(commitment))
+      .accountsPartial({ registry, authority: authority.publicKey, config: configPda })
+      .rpc();
+  }
+
+  function freezeRegistry(registry: PublicKey) {
+    return program.methods
+      .freezeRegistry()
+      .accountsPartial({ registry, authority: authority.publicKey })
+      .rpc();
+  }
+
+  type ProposalOptions = {
+    votersRoot?: Buffer;
+    title?: string;
+    description?: string;
+    votingEndsAt?: BN;
+    numOptions?: number;
+    optionLabels?: string[];
+    approvalOption?: number | null;
+    acceptRecentRoots?: boolean;
+    eligibleVoters?: BN | null;
+    voterGroups?: any[];
+    weighted?: boolean;
+    ballotMode?: any;
+    allowRevision?: boolean;
+    dao?: PublicKey | null;
+    prerequisite?: PublicKey | null;
+    registry?: PublicKey | null;
+    encryptedTally?: PublicKey | null;
+    signer?: Keypair;
+  };
+
+  function findEncryptedTallyPda(proposal: PublicKey): PublicKey {
+    return PublicKey.findProgramAddressSync(
+      [Buffer.from("encrypted_tally"), proposal.toBuffer()],
       program.programId
+    )[0];
+  }
+
+  /** Create a proposal, defaulting to a two-option Yes/No single-choice vote */
+  async function createProposal(
+    id: BN,
+    options: ProposalOptions = {}
+  ): Promise<PublicKey> {
+    const pda = findProposalPda(id);
+    const numOptions = options.numOptions ?? 2;
+    const creator = options.signer?.publicKey ?? authority.publicKey;
+
+    await program.methods
+      .createProposal(
+        id,
+        Array.from(options.votersRoot ?? votersRoot),
+        options.title ?? `Proposal ${id.toString()}`,
+        options.description ?? "Created by the test suite",
+        options.votingEndsAt ?? new BN(now() + 3600),
+        numOptions,
+        options.optionLabels ??
+          (numOptions === 2
+            ? ["Yes", "No"]
+            : Array.from({ length: numOptions }, (_, i) => `Option ${i}`)),
+        {
+          approvalOption: options.approvalOption ?? null,
+          acceptRecentRoots: options.acceptRecentRoots ?? false,
+          eligibleVoters:
+            options.eligibleVoters === undefined
+              ? options.registry
+                ? null
+                : new BN(10)
+              : options.eligibleVoters,
+          voterGroups: options.voterGroups ?? [],
+          weighted: options.weighted ?? false,
+          ballotMode: options.ballotMode ?? { single: {} },
+          allowRevision: options.allowRevision ?? false,
+        }
+      )
+      .accountsPartial({
+        proposal: pda,
+        authority: creator,
+        dao: options.dao ?? null,
+        prerequisite: options.prerequisite ?? null,
+        registry: options.registry ?? null,
+        encryptedTally:
+          options.encryptedTally !== undefined
+            ? options.encryptedTally
+            : options.ballotMode?.encrypted
+            ? findEncryptedTallyPda(pda)
+            : null,
+        config: configPda,
+        systemProgram: SystemProgram.programId,
+      })
+      .signers(options.signer ? [options.signer] : [])
+      .rpc();
+    return pda;
+  }
+
+  type VoteOptions = {
+    votersRoot?: number[] | null;
+    prerequisite?: PublicKey | null;
+    registry?: PublicKey | null;
+    proof?: Buffer;
+    group?: number;
+    weight?: BN;
+  };
+
+  function castVote(
+    proposal: PublicKey,
+    nullifier: Buffer,
+    vote: number,
+    options: VoteOptions = {}
+  ) {
+    const group = options.group ?? 0;
+    return program.methods
+      .castVote(
+        Array.from(nullifier),
+        vote,
+        options.proof ?? mockProof,
+        options.votersRoot ?? null,
+        group,
+        options.weight ?? new BN(0)
+      )
+      .accountsPartial({
+        proposal,
+        nullifierAccount: findNullifierPda(proposal, nullifier, group),
+        prerequisite: options.prerequisite ?? null,
+        registry: options.registry ?? null,
+        payer: authority.publicKey,
+        config: configPda,
+        systemProgram: SystemProgram.programId,
+      })
+      .rpc();
+  }
+
+  function castVectorVote(
+    proposal: PublicKey,
+    nullifier: Buffer,
+    votes: number[],
+    options: VoteOptions = {}
+  ) {
+    const group = options.group ?? 0;
+    return program.methods
+      .castVectorVote(
+        Array.from(nullifier),
+        votes,
+        options.proof ?? mockProof,
+        options.votersRoot ?? null,
+        group,
+        options.weight ?? new BN(0)
+      )
+      .accountsPartial({
+        proposal,
+        nullifierAccount: findNullifierPda(proposal, nullifier, group),
+        prerequisite: options.prerequisite ?? null,
+        registry: options.registry ?? null,
+        payer: authority.publicKey,
+        config: configPda,
+        systemProgram: SystemProgram.programId,
+      })
+      .rpc();
+  }
+
+  function finalize(proposal: PublicKey, encryptedTally: PublicKey | null = null) {
+    return program.methods
+      .finalizeProposal()
+      .accountsPartial({ proposal, encryptedTally, authority: authority.publicKey })
+      .rpc();
+  }
+
+  before(async () => {
+    proposalPda = findProposalPda(proposalId);
+
+    poseidon = await buildPoseidon();
+    zeroHashes = [0n];
+    for (let depth = 0; depth < 20; depth++) {
+      zeroHashes.push(poseidonHash(zeroHashes[depth], zeroHashes[depth]));
+    }
+
+    // Every pausable instruction requires the program config; only the
+    // upgrade authority (the provider wallet on localnet) can create it
+    const [programData] = PublicKey.findProgramAddressSync(
+      [program.programId.toBuffer()],
+      BPF_LOADER_UPGRADEABLE_ID
     );
+    await program.methods
+      .initializeConfig(authority.publicKey)
+      .accountsPartial({
+        config: configPda,
+        program: program.programId,
+        programData,
+        authority: authority.publicKey,
+        systemProgram: SystemProgram.programId,
+      })
+      .rpc();
   });
 
   describe("create_proposal", () => {
     it("creates a proposal with valid parameters", async () => {
       const title = "Test Proposal";
       const description = "A test proposal for unit testing";
-      const votingEndsAt = new BN(Math.floor(Date.now() / 1000) + 3600); // 1 hour from now
-
-      const tx = await program.methods
-        .createProposal(
-          proposalId,
-          Array.from(votersRoot),
-          title,
-          description,
-          votingEndsAt
-        )
-        .accounts({
-          proposal: proposalPda,
-          authority: authority.publicKey,
-          systemProgram: SystemProgram.programId,
-        })
-        .rpc();
 
-      console.log("Create proposal tx:", tx);
+      await createProposal(proposalId, { title, description });
 
       // Fetch and verify proposal state
       const proposal = await program.account.proposal.fetch(proposalPda);
@@ -56,121 +502,73 @@ describe("voting_program", () => {
       expect(proposal.proposalId.toNumber()).to.equal(1);
       expect(proposal.title).to.equal(title);
       expect(proposal.description).to.equal(description);
-      expect(proposal.yesVotes.toNumber()).to.equal(0);
-      expect(proposal.noVotes.toNumber()).to.equal(0);
+      expect(proposal.numOptions).to.equal(2);
+      expect(proposal.optionLabels).to.deep.equal(["Yes", "No"]);
+      expect(proposal.voteCounts.map((count) => count.toNumber())).to.deep.equal(
+        [0, 0, 0, 0, 0, 0, 0, 0]
+      );
+      expect(proposal.eligibleVoters.toNumber()).to.equal(10);
       expect(proposal.isFinalized).to.be.false;
+      expect(proposal.outcome).to.deep.equal({ pending: {} });
       expect(Buffer.from(proposal.votersRoot)).to.deep.equal(votersRoot);
     });
 
     it("fails to create duplicate proposal", async () => {
       try {
-        await program.methods
-          .createProposal(
-            proposalId,
-            Array.from(votersRoot),
-            "Duplicate",
-            "Should fail",
-            new BN(Math.floor(Date.now() / 1000) + 3600)
-          )
-          .accounts({
-            proposal: proposalPda,
-            authority: authority.publicKey,
-            systemProgram: SystemProgram.programId,
-          })
-          .rpc();
-
+        await createProposal(proposalId, { title: "Duplicate" });
         expect.fail("Should have thrown error for duplicate proposal");
       } catch (err) {
         // Expected: account already exists
         expect(err.toString()).to.include("already in use");
       }
     });
-  });
-
-  describe("cast_vote", () => {
-    const nullifier1 = Buffer.alloc(32);
-    nullifier1.fill(0x11);
-
-    const nullifier2 = Buffer.alloc(32);
-    nullifier2.fill(0x22);
 
-    let nullifierPda1: PublicKey;
-    let nullifierPda2: PublicKey;
-
-    before(async () => {
-      [nullifierPda1] = PublicKey.findProgramAddressSync(
-        [Buffer.from("nullifier"), proposalPda.toBuffer(), nullifier1],
-        program.programId
+    it("rejects a proposal without an eligible voter count", async () => {
+      await expectError(
+        createProposal(new BN(2), { eligibleVoters: null }),
+        "EligibleVotersRequired"
       );
+    });
 
-      [nullifierPda2] = PublicKey.findProgramAddressSync(
-        [Buffer.from("nullifier"), proposalPda.toBuffer(), nullifier2],
-        program.programId
+    it("rejects mismatched option labels", async () => {
+      await expectError(
+        createProposal(new BN(3), { numOptions: 3, optionLabels: ["A", "B"] }),
+        "OptionLabelsMismatch"
       );
     });
+  });
 
-    it("casts a YES vote with valid proof", async () => {
-      const vote = 1; // YES
-      const proofData = Buffer.alloc(192); // Mock proof
-
-      const tx = await program.methods
-        .castVote(Array.from(nullifier1), vote, proofData)
-        .accounts({
-          proposal: proposalPda,
-          nullifierAccount: nullifierPda1,
-          payer: authority.publicKey,
-          systemProgram: SystemProgram.programId,
-        })
-        .rpc();
+  describe("cast_vote", () => {
+    const nullifier1 = filled(0x11);
+    const nullifier2 = filled(0x22);
 
-      console.log("Cast YES vote tx:", tx);
+    it("casts a vote for option 1 with valid proof", async () => {
+      const tx = await castVote(proposalPda, nullifier1, 1);
+      console.log("Cast vote tx:", tx);
 
       // Verify vote recorded
       const proposal = await program.account.proposal.fetch(proposalPda);
-      expect(proposal.yesVotes.toNumber()).to.equal(1);
-      expect(proposal.noVotes.toNumber()).to.equal(0);
+      expect(proposal.voteCounts[0].toNumber()).to.equal(0);
+      expect(proposal.voteCounts[1].toNumber()).to.equal(1);
 
       // Verify nullifier recorded
       const nullifierAccount = await program.account.nullifierAccount.fetch(
-        nullifierPda1
+        findNullifierPda(proposalPda, nullifier1)
       );
       expect(Buffer.from(nullifierAccount.nullifier)).to.deep.equal(nullifier1);
     });
 
-    it("casts a NO vote with valid proof", async () => {
-      const vote = 0; // NO
-      const proofData = Buffer.alloc(192);
-
-      const tx = await program.methods
-        .castVote(Array.from(nullifier2), vote, proofData)
-        .accounts({
-          proposal: proposalPda,
-          nullifierAccount: nullifierPda2,
-          payer: authority.publicKey,
-          systemProgram: SystemProgram.programId,
-        })
-        .rpc();
-
-      console.log("Cast NO vote tx:", tx);
+    it("casts a vote for option 0 with valid proof", async () => {
+      await castVote(proposalPda, nullifier2, 0);
 
-      // Verify vote recorded
       const proposal = await program.account.proposal.fetch(proposalPda);
-      expect(proposal.yesVotes.toNumber()).to.equal(1);
-      expect(proposal.noVotes.toNumber()).to.equal(1);
+      expect(proposal.voteCounts[0].toNumber()).to.equal(1);
+      expect(proposal.voteCounts[1].toNumber()).to.equal(1);
     });
 
     it("rejects double voting with same nullifier", async () => {
       try {
-        await program.methods
-          .castVote(Array.from(nullifier1), 1, Buffer.alloc(192))
-          .accounts({
-            proposal: proposalPda,
-            nullifierAccount: nullifierPda1,
-            payer: authority.publicKey,
-            systemProgram: SystemProgram.programId,
-          })
-          .rpc();
-
+        await castVote(proposalPda, nullifier1, 1);
         expect.fail("Should have rejected double vote");
       } catch (err) {
         // Expected: nullifier account already exists
@@ -179,89 +577,43 @@ describe("voting_program", () => {
     });
 
     it("rejects invalid vote value", async () => {
-      const invalidNullifier = Buffer.alloc(32);
-      invalidNullifier.fill(0x33);
-
-      const [invalidNullifierPda] = PublicKey.findProgramAddressSync(
-        [Buffer.from("nullifier"), proposalPda.toBuffer(), invalidNullifier],
-        program.programId
-      );
+      await expectError(castVote(proposalPda, filled(0x33), 2), "InvalidVote");
+    });
 
+    it("rejects oversized proof data", async () => {
       try {
-        await program.methods
-          .castVote(Array.from(invalidNullifier), 2, Buffer.alloc(192)) // Invalid vote value
-          .accounts({
-            proposal: proposalPda,
-            nullifierAccount: invalidNullifierPda,
-            payer: authority.publicKey,
-            systemProgram: SystemProgram.programId,
-          })
-          .rpc();
-
-        expect.fail("Should have rejected invalid vote");
+        await castVote(proposalPda, filled(0x44), 1, {
+          proof: Buffer.alloc(600), // Larger than MAX_PROOF_SIZE (512)
+        });
+        expect.fail("Should have rejected oversized proof");
       } catch (err) {
-        // Error should indicate invalid vote (error code 6003)
         const errStr = err.toString();
         expect(
-          errStr.includes("InvalidVote") || errStr.includes("6003")
+          errStr.includes("ProofTooLarge") ||
+            errStr.includes("RangeError") ||
+            errStr.includes("too large")
         ).to.be.true;
       }
     });
 
-    it("rejects oversized proof data", async () => {
-      const oversizedNullifier = Buffer.alloc(32);
-      oversizedNullifier.fill(0x44);
-
-      const [oversizedNullifierPda] = PublicKey.findProgramAddressSync(
-        [Buffer.from("nullifier"), proposalPda.toBuffer(), oversizedNullifier],
-        program.programId
+    it("rejects a voter group on a single-group proposal", async () => {
+      await expectError(
+        castVote(proposalPda, filled(0x46), 1, { group: 1 }),
+        "InvalidVoterGroup"
       );
+    });
 
-      try {
-        await program.methods
-          .castVote(
-            Array.from(oversizedNullifier),
-            1,
-            Buffer.alloc(600) // Larger than MAX_PROOF_SIZE (512)
-          )
-          .accounts({
-            proposal: proposalPda,
-            nullifierAccount: oversizedNullifierPda,
-            payer: authority.publicKey,
-            systemProgram: SystemProgram.programId,
-          })
-          .rpc();
-
-        expect.fail("Should have rejected oversized proof");
-      } catch (err) {
-        // Error should indicate proof too large (error code 6004)
-        const errStr = err.toString();
-        expect(
-          errStr.includes("ProofTooLarge") || errStr.includes("6004") || errStr.includes("RangeError")
-        ).to.be.true;
-      }
+    it("rejects a weight on an unweighted proposal", async () => {
+      await expectError(
+        castVote(proposalPda, filled(0x47), 1, { weight: new BN(5) }),
+        "InvalidWeight"
+      );
     });
   });
 
   describe("finalize_proposal", () => {
     it("rejects finalization before deadline", async () => {
-      try {
-        await program.methods
-          .finalizeProposal()
-          .accounts({
-            proposal: proposalPda,
-            authority: authority.publicKey,
-          })
-          .rpc();
-
-        expect.fail("Should have rejected early finalization");
-      } catch (err) {
-        // Error should indicate voting not ended (error code 6001)
-        const errStr = err.toString();
-        expect(
-          errStr.includes("VotingNotEnded") || errStr.includes("6001")
-        ).to.be.true;
-      }
+      await expectError(finalize(proposalPda), "VotingNotEnded");
     });
   });
 
@@ -270,115 +622,38 @@ describe("voting_program", () => {
     let expiredProposalPda: PublicKey;
 
     before(async () => {
-      [expiredProposalPda] = PublicKey.findProgramAddressSync(
-        [
-          Buffer.from("proposal"),
-          expiredProposalId.toArrayLike(Buffer, "le", 8),
-        ],
-        program.programId
-      );
-
       // Create an already-expired proposal
-      const pastDeadline = new BN(Math.floor(Date.now() / 1000) - 1);
-
-      await program.methods
-        .createProposal(
-          expiredProposalId,
-          Array.from(votersRoot),
-          "Expired Proposal",
-          "Already past deadline",
-          pastDeadline
-        )
-        .accounts({
-          proposal: expiredProposalPda,
-          authority: authority.publicKey,
-          systemProgram: SystemProgram.programId,
-        })
-        .rpc();
+      expiredProposalPda = await createProposal(expiredProposalId, {
+        title: "Expired Proposal",
+        votingEndsAt: new BN(now() - 1),
+      });
     });
 
     it("rejects voting after deadline", async () => {
-      const lateNullifier = Buffer.alloc(32);
-      lateNullifier.fill(0x55);
-
-      const [lateNullifierPda] = PublicKey.findProgramAddressSync(
-        [Buffer.from("nullifier"), expiredProposalPda.toBuffer(), lateNullifier],
-        program.programId
+      await expectError(
+        castVote(expiredProposalPda, filled(0x55), 1),
+        "VotingEnded"
       );
-
-      try {
-        await program.methods
-          .castVote(Array.from(lateNullifier), 1, Buffer.alloc(192))
-          .accounts({
-            proposal: expiredProposalPda,
-            nullifierAccount: lateNullifierPda,
-            payer: authority.publicKey,
-            systemProgram: SystemProgram.programId,
-          })
-          .rpc();
-
-        expect.fail("Should have rejected late vote");
-      } catch (err) {
-        expect(err.toString()).to.include("VotingEnded");
-      }
     });
 
     it("allows finalization after deadline", async () => {
-      const tx = await program.methods
-        .finalizeProposal()
-        .accounts({
-          proposal: expiredProposalPda,
-          authority: authority.publicKey,
-        })
-        .rpc();
-
+      const tx = await finalize(expiredProposalPda);
       console.log("Finalize proposal tx:", tx);
 
       const proposal = await program.account.proposal.fetch(expiredProposalPda);
       expect(proposal.isFinalized).to.be.true;
+      expect(proposal.outcome).to.deep.equal({ noVotes: {} });
+      expect(proposal.turnoutBps).to.equal(0);
     });
 
     it("rejects double finalization", async () => {
-      try {
-        await program.methods
-          .finalizeProposal()
-          .accounts({
-            proposal: expiredProposalPda,
-            authority: authority.publicKey,
-          })
-          .rpc();
-
-        expect.fail("Should have rejected double finalization");
-      } catch (err) {
-        expect(err.toString()).to.include("ProposalFinalized");
-      }
+      await expectError(finalize(expiredProposalPda), "ProposalFinalized");
     });
 
     it("rejects voting on finalized proposal", async () => {
       // Even with deadline check passing (if it could), finalized check blocks it
-      const finalizedNullifier = Buffer.alloc(32);
-      finalizedNullifier.fill(0x66);
-
-      const [finalizedNullifierPda] = PublicKey.findProgramAddressSync(
-        [
-          Buffer.from("nullifier"),
-          expiredProposalPda.toBuffer(),
-          finalizedNullifier,
-        ],
-        program.programId
-      );
-
       try {
-        await program.methods
-          .castVote(Array.from(finalizedNullifier), 1, Buffer.alloc(192))
-          .accounts({
-            proposal: expiredProposalPda,
-            nullifierAccount: finalizedNullifierPda,
-            payer: authority.publicKey,
-            systemProgram: SystemProgram.programId,
-          })
-          .rpc();
-
+        await castVote(expiredProposalPda, filled(0x66), 1);
         expect.fail("Should have rejected vote on finalized proposal");
       } catch (err) {
         // Could be either VotingEnded or ProposalFinalized depending on check order
@@ -390,45 +665,136 @@ describe("voting_program", () => {
     });
   });
 
+  describe("finalization outcome", () => {
+    const outcomeProposalId = new BN(1000);
+    let outcomePda: PublicKey;
+    let votingEndsAt: number;
+
+    before(async () => {
+      votingEndsAt = now() + 3;
+      outcomePda = await createProposal(outcomeProposalId, {
+        votingEndsAt: new BN(votingEndsAt),
+        numOptions: 3,
+        eligibleVoters: new BN(4),
+      });
+      await castVote(outcomePda, filled(0x71), 2);
+      await castVote(outcomePda, filled(0x72), 2);
+      await castVote(outcomePda, filled(0x73), 0);
+    });
+
+    it("records the winner and turnout", async () => {
+      await waitUntil(votingEndsAt);
+      await finalize(outcomePda);
+
+      const proposal = await program.account.proposal.fetch(outcomePda);
+      expect(proposal.outcome).to.deep.equal({ succeeded: {} });
+      expect(proposal.winningOption).to.equal(2);
+      // 3 ballots of 4 eligible voters
+      expect(proposal.turnoutBps).to.equal(7500);
+    });
+  });
+
+  describe("prerequisites", () => {
+    let rejectedPda: PublicKey;
+    let approvedPda: PublicKey;
+    let blockedPda: PublicKey;
+    let unblockedPda: PublicKey;
+    let votingEndsAt: number;
+
+    before(async () => {
+      votingEndsAt = now() + 4;
+      rejectedPda = await createProposal(new BN(1200), {
+        votingEndsAt: new BN(votingEndsAt),
+        approvalOption: 0,
+      });
+      approvedPda = await createProposal(new BN(1201), {
+        votingEndsAt: new BN(votingEndsAt),
+        approvalOption: 0,
+      });
+      blockedPda = await createProposal(new BN(1202), {
+        prerequisite: rejectedPda,
+      });
+      unblockedPda = await createProposal(new BN(1203), {
+        prerequisite: approvedPda,
+      });
+
+      // "No" wins the first prerequisite, "Yes" the second
+      await castVote(rejectedPda, filled(0x81), 1);
+      await castVote(rejectedPda, filled(0x82), 1);
+      await castVote(rejectedPda, filled(0x83), 0);
+      await castVote(approvedPda, filled(0x84), 0);
+    });
+
+    it("rejects an approval option out of range", async () => {
+      await expectError(
+        createProposal(new BN(1204), { approvalOption: 2 }),
+        "InvalidApprovalOption"
+      );
+    });
+
+    it("rejects a prerequisite without an approval option", async () => {
+      const plainPda = await createProposal(new BN(1205));
+      await expectError(
+        createProposal(new BN(1206), { prerequisite: plainPda }),
+        "PrerequisiteWithoutApprovalOption"
+      );
+    });
+
+    it("blocks voting until the prerequisite is finalized", async () => {
+      await expectError(
+        castVote(unblockedPda, filled(0x85), 0, { prerequisite: approvedPda }),
+        "PrerequisiteNotFinalized"
+      );
+    });
+
+    it("keeps dependents blocked when the prerequisite's No option wins", async () => {
+      await waitUntil(votingEndsAt);
+      await finalize(rejectedPda);
+      await finalize(approvedPda);
+
+      const rejected = await program.account.proposal.fetch(rejectedPda);
+      expect(rejected.outcome).to.deep.equal({ succeeded: {} });
+      expect(rejected.winningOption).to.equal(1);
+
+      await expectError(
+        castVote(blockedPda, filled(0x86), 0, { prerequisite: rejectedPda }),
+        "PrerequisiteNotPassed"
+      );
+      await expectError(
+        createProposal(new BN(1207), { prerequisite: rejectedPda }),
+        "PrerequisiteNotPassed"
+      );
+    });
+
+    it("unblocks dependents once the approval option wins", async () => {
+      await castVote(unblockedPda, filled(0x87), 0, { prerequisite: approvedPda });
+
+      const proposal = await program.account.proposal.fetch(unblockedPda);
+      expect(proposal.voteCounts[0].toNumber()).to.equal(1);
+    });
+  });
+
+
   describe("authority checks", () => {
     const unauthorizedProposalId = new BN(888);
     let unauthorizedProposalPda: PublicKey;
     const unauthorized = Keypair.generate();
 
     before(async () => {
-      [unauthorizedProposalPda] = PublicKey.findProgramAddressSync(
-        [
-          Buffer.from("proposal"),
-          unauthorizedProposalId.toArrayLike(Buffer, "le", 8),
-        ],
-        program.programId
-      );
-
       // Create proposal with authority's wallet
-      const pastDeadline = new BN(Math.floor(Date.now() / 1000) - 1);
-
-      await program.methods
-        .createProposal(
-          unauthorizedProposalId,
-          Array.from(votersRoot),
-          "Authority Test",
-          "Testing authority checks",
-          pastDeadline
-        )
-        .accounts({
-          proposal: unauthorizedProposalPda,
-          authority: authority.publicKey,
-          systemProgram: SystemProgram.programId,
-        })
-        .rpc();
+      unauthorizedProposalPda = await createProposal(unauthorizedProposalId, {
+        title: "Authority Test",
+        votingEndsAt: new BN(now() - 1),
+      });
     });
 
     it("rejects finalization by non-authority", async () => {
       try {
         await program.methods
           .finalizeProposal()
-          .accounts({
+          .accountsPartial({
             proposal: unauthorizedProposalPda,
+            encryptedTally: null,
             authority: unauthorized.publicKey,
           })
           .signers([unauthorized])
@@ -440,4 +806,2160 @@ describe("voting_program", () => {
       }
     });
   });
+
+  describe("authority transfer", () => {
+    const nominee = Keypair.generate();
+    const stranger = Keypair.generate();
+
+    it("hands a proposal over only after the nominee accepts", async () => {
+      const pda = await createProposal(new BN(1300));
+
+      await program.methods
+        .proposeAuthorityTransfer(nominee.publicKey)
+        .accountsPartial({ proposal: pda, authority: authority.publicKey })
+        .rpc();
+      let proposal = await program.account.proposal.fetch(pda);
+      expect(proposal.authority.toBase58()).to.equal(authority.publicKey.toBase58());
+      expect(proposal.pendingAuthority.toBase58()).to.equal(nominee.publicKey.toBase58());
+
+      await expectError(
+        program.methods
+          .acceptAuthorityTransfer()
+          .accountsPartial({ proposal: pda, newAuthority: stranger.publicKey })
+          .signers([stranger])
+          .rpc(),
+        "NotPendingAuthority"
+      );
+
+      await program.methods
+        .acceptAuthorityTransfer()
+        .accountsPartial({ proposal: pda, newAuthority: nominee.publicKey })
+        .signers([nominee])
+        .rpc();
+      proposal = await program.account.proposal.fetch(pda);
+      expect(proposal.authority.toBase58()).to.equal(nominee.publicKey.toBase58());
+      expect(proposal.pendingAuthority).to.be.null;
+
+      // The previous authority has no say any more
+      await expectError(
+        program.methods
+          .proposeAuthorityTransfer(authority.publicKey)
+          .accountsPartial({ proposal: pda, authority: authority.publicKey })
+          .rpc(),
+        "ConstraintHasOne"
+      );
+    });
+
+    it("hands a DAO over only after the nominee accepts", async () => {
+      const daoId = new BN(3);
+      const [daoPda] = PublicKey.findProgramAddressSync(
+        [Buffer.from("dao"), daoId.toArrayLike(Buffer, "le", 8)],
+        program.programId
+      );
+      const [treasuryPda] = PublicKey.findProgramAddressSync(
+        [Buffer.from("treasury"), daoPda.toBuffer()],
+        program.programId
+      );
+      await program.methods
+        .createDao(daoId, "Transfer DAO", 0)
+        .accountsPartial({
+          dao: daoPda,
+          treasury: treasuryPda,
+          authority: authority.publicKey,
+          config: configPda,
+          systemProgram: SystemProgram.programId,
+        })
+        .rpc();
+
+      await expectError(
+        program.methods
+          .proposeDaoAuthorityTransfer(stranger.publicKey)
+          .accountsPartial({ dao: daoPda, authority: stranger.publicKey })
+          .signers([stranger])
+          .rpc(),
+        "Unauthorized"
+      );
+
+      await program.methods
+        .proposeDaoAuthorityTransfer(nominee.publicKey)
+        .accountsPartial({ dao: daoPda, authority: authority.publicKey })
+        .rpc();
+      await program.methods
+        .acceptDaoAuthorityTransfer()
+        .accountsPartial({ dao: daoPda, newAuthority: nominee.publicKey })
+        .signers([nominee])
+        .rpc();
+
+      const dao = await program.account.dao.fetch(daoPda);
+      expect(dao.authority.toBase58()).to.equal(nominee.publicKey.toBase58());
+      expect(dao.pendingAuthority).to.be.null;
+
+      await expectError(
+        program.methods
+          .proposeDaoAuthorityTransfer(authority.publicKey)
+          .accountsPartial({ dao: daoPda, authority: authority.publicKey })
+          .rpc(),
+        "Unauthorized"
+      );
+    });
+
+    it("hands a registry over only after the nominee accepts", async () => {
+      const registryId = new BN(1);
+      const registryPda = findRegistryPda(registryId);
+      await createRegistry(registryId);
+
+      await program.methods
+        .proposeRegistryAuthorityTransfer(nominee.publicKey)
+        .accountsPartial({ registry: registryPda, authority: authority.publicKey })
+        .rpc();
+
+      // Nominating again replaces the pending key
+      await program.methods
+        .proposeRegistryAuthorityTransfer(stranger.publicKey)
+        .accountsPartial({ registry: registryPda, authority: authority.publicKey })
+        .rpc();
+      await expectError(
+        program.methods
+          .acceptRegistryAuthorityTransfer()
+          .accountsPartial({ registry: registryPda, newAuthority: nominee.publicKey })
+          .signers([nominee])
+          .rpc(),
+        "NotPendingAuthority"
+      );
+
+      await program.methods
+        .acceptRegistryAuthorityTransfer()
+        .accountsPartial({ registry: registryPda, newAuthority: stranger.publicKey })
+        .signers([stranger])
+        .rpc();
+      const registry = await program.account.voterRegistry.fetch(registryPda);
+      expect(registry.authority.toBase58()).to.equal(stranger.publicKey.toBase58());
+
+      await expectError(registerCommitment(registryPda, 7n), "Unauthorized");
+      await expectError(freezeRegistry(registryPda), "Unauthorized");
+    });
+  });
+
+  describe("emergency pause", () => {
+    const pausedProposalId = new BN(1100);
+    let pausedProposalPda: PublicKey;
+    let expiredPda: PublicKey;
+    const stranger = Keypair.generate();
+
+    function setPaused(paused: boolean, guardian?: Keypair) {
+      const accounts = {
+        config: configPda,
+        guardian: guardian?.publicKey ?? authority.publicKey,
+      };
+      const signers = guardian ? [guardian] : [];
+      return paused
+        ? program.methods.pause().accountsPartial(accounts).signers(signers).rpc()
+        : program.methods.unpause().accountsPartial(accounts).signers(signers).rpc();
+    }
+
+    before(async () => {
+      pausedProposalPda = await createProposal(pausedProposalId);
+      expiredPda = await createProposal(new BN(1101), {
+        votingEndsAt: new BN(now() - 1),
+      });
+    });
+
+    after(async () => {
+      const config = await program.account.programConfig.fetch(configPda);
+      if (config.paused) {
+        await setPaused(false);
+      }
+    });
+
+    it("initialized the config with the provider wallet as guardian", async () => {
+      const config = await program.account.programConfig.fetch(configPda);
+      expect(config.guardian.toBase58()).to.equal(
+        authority.publicKey.toBase58()
+      );
+      expect(config.paused).to.be.false;
+    });
+
+    it("rejects pausing by anyone but the guardian", async () => {
+      await expectError(setPaused(true, stranger), "Unauthorized");
+    });
+
+    it("lets the guardian pause the program", async () => {
+      await setPaused(true);
+
+      const config = await program.account.programConfig.fetch(configPda);
+      expect(config.paused).to.be.true;
+    });
+
+    it("rejects pausing twice", async () => {
+      await expectError(setPaused(true), "ProgramPaused");
+    });
+
+    it("blocks voting while paused", async () => {
+      await expectError(
+        castVote(pausedProposalPda, filled(0x81), 0),
+        "ProgramPaused"
+      );
+    });
+
+    it("blocks proposal creation while paused", async () => {
+      await expectError(createProposal(new BN(1102)), "ProgramPaused");
+    });
+
+    it("still allows finalization while paused", async () => {
+      await finalize(expiredPda);
+
+      const proposal = await program.account.proposal.fetch(expiredPda);
+      expect(proposal.isFinalized).to.be.true;
+    });
+
+    it("rejects unpausing by anyone but the guardian", async () => {
+      await expectError(setPaused(false, stranger), "Unauthorized");
+    });
+
+    it("lets the guardian unpause and voting resumes", async () => {
+      await setPaused(false);
+      await castVote(pausedProposalPda, filled(0x82), 0);
+
+      const proposal = await program.account.proposal.fetch(pausedProposalPda);
+      expect(proposal.voteCounts[0].toNumber()).to.equal(1);
+    });
+
+    it("rejects unpausing when not paused", async () => {
+      await expectError(setPaused(false), "ProgramNotPaused");
+    });
+  });
+
+  describe("dao treasury", () => {
+    const daoId = new BN(1);
+    const recipient = Keypair.generate();
+    const stranger = Keypair.generate();
+    let daoPda: PublicKey;
+    let treasuryPda: PublicKey;
+    let treasuryProposalPda: PublicKey;
+    let lowTurnoutPda: PublicKey;
+    let votingEndsAt: number;
+
+    function findActionPda(proposal: PublicKey, index: number): PublicKey {
+      return PublicKey.findProgramAddressSync(
+        [Buffer.from("action"), proposal.toBuffer(), Buffer.from([index])],
+        program.programId
+      )[0];
+    }
+
+    function addAction(proposal: PublicKey, index: number, option: number, action: any) {
+      return program.methods
+        .addProposalAction(option, action)
+        .accountsPartial({
+          proposal,
+          action: findActionPda(proposal, index),
+          authority: authority.publicKey,
+          config: configPda,
+          systemProgram: SystemProgram.programId,
+        })
+        .rpc();
+    }
+
+    function executeAction(
+      proposal: PublicKey,
+      index: number,
+      remaining: PublicKey[]
+    ) {
+      return program.methods
+        .executeAction()
+        .accountsPartial({
+          proposal,
+          dao: daoPda,
+          treasury: treasuryPda,
+          action: findActionPda(proposal, index),
+          prerequisite: null,
+          config: configPda,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+        })
+        .remainingAccounts(
+          remaining.map((pubkey) => ({ pubkey, isSigner: false, isWritable: true }))
+        )
+        .rpc();
+    }
+
+    function transferSol(lamports: number) {
+      return {
+        transferSol: { recipient: recipient.publicKey, lamports: new BN(lamports) },
+      };
+    }
+
+    before(async () => {
+      [daoPda] = PublicKey.findProgramAddressSync(
+        [Buffer.from("dao"), daoId.toArrayLike(Buffer, "le", 8)],
+        program.programId
+      );
+      [treasuryPda] = PublicKey.findProgramAddressSync(
+        [Buffer.from("treasury"), daoPda.toBuffer()],
+        program.programId
+      );
+
+      await program.methods
+        .createDao(daoId, "Test DAO", 2000)
+        .accountsPartial({
+          dao: daoPda,
+          treasury: treasuryPda,
+          authority: authority.publicKey,
+          config: configPda,
+          systemProgram: SystemProgram.programId,
+        })
+        .rpc();
+
+      // Fund the treasury vault
+      await provider.sendAndConfirm(
+        new Transaction().add(
+          SystemProgram.transfer({
+            fromPubkey: authority.publicKey,
+            toPubkey: treasuryPda,
+            lamports: LAMPORTS_PER_SOL,
+          })
+        )
+      );
+
+      votingEndsAt = now() + 6;
+      treasuryProposalPda = await createProposal(new BN(2000), {
+        votingEndsAt: new BN(votingEndsAt),
+        dao: daoPda,
+      });
+      lowTurnoutPda = await createProposal(new BN(2002), {
+        votingEndsAt: new BN(votingEndsAt),
+        eligibleVoters: new BN(100),
+        dao: daoPda,
+      });
+    });
+
+    it("creates the DAO with its treasury vault", async () => {
+      const dao = await program.account.dao.fetch(daoPda);
+      expect(dao.authority.toBase58()).to.equal(authority.publicKey.toBase58());
+      expect(dao.name).to.equal("Test DAO");
+      expect(dao.quorumBps).to.equal(2000);
+
+      const balance = await provider.connection.getBalance(treasuryPda);
+      expect(balance).to.equal(LAMPORTS_PER_SOL);
+    });
+
+    it("rejects a quorum above 100%", async () => {
+      const id = new BN(9);
+      const [pda] = PublicKey.findProgramAddressSync(
+        [Buffer.from("dao"), id.toArrayLike(Buffer, "le", 8)],
+        program.programId
+      );
+      const [treasury] = PublicKey.findProgramAddressSync(
+        [Buffer.from("treasury"), pda.toBuffer()],
+        program.programId
+      );
+      await expectError(
+        program.methods
+          .createDao(id, "Bad DAO", 10_001)
+          .accountsPartial({
+            dao: pda,
+            treasury,
+            authority: authority.publicKey,
+            config: configPda,
+            systemProgram: SystemProgram.programId,
+          })
+          .rpc(),
+        "InvalidQuorum"
+      );
+    });
+
+    it("rejects linking a proposal to a DAO by another authority", async () => {
+      await provider.connection.confirmTransaction(
+        await provider.connection.requestAirdrop(stranger.publicKey, LAMPORTS_PER_SOL)
+      );
+      await expectError(
+        createProposal(new BN(2001), { dao: daoPda, signer: stranger }),
+        "Unauthorized"
+      );
+    });
+
+    it("rejects actions on proposals without a DAO", async () => {
+      await expectError(
+        addAction(proposalPda, 0, 0, transferSol(1000)),
+        "ProposalHasNoDao"
+      );
+    });
+
+    it("rejects zero-amount actions", async () => {
+      await expectError(
+        addAction(treasuryProposalPda, 0, 0, transferSol(0)),
+        "InvalidActionAmount"
+      );
+    });
+
+    it("rejects actions on options that don't exist", async () => {
+      await expectError(
+        addAction(treasuryProposalPda, 0, 2, transferSol(1000)),
+        "InvalidActionOption"
+      );
+    });
+
+    it("attaches an action to each option", async () => {
+      await addAction(treasuryProposalPda, 0, 0, transferSol(LAMPORTS_PER_SOL / 10));
+      await addAction(treasuryProposalPda, 1, 1, transferSol(LAMPORTS_PER_SOL / 5));
+
+      const proposal = await program.account.proposal.fetch(treasuryProposalPda);
+      expect(proposal.actionCount).to.equal(2);
+
+      const action = await program.account.proposalAction.fetch(
+        findActionPda(treasuryProposalPda, 0)
+      );
+      expect(action.option).to.equal(0);
+      expect(action.executed).to.be.false;
+      expect(action.action.transferSol.recipient.toBase58()).to.equal(
+        recipient.publicKey.toBase58()
+      );
+    });
+
+    it("rejects executing before finalization", async () => {
+      await expectError(
+        executeAction(treasuryProposalPda, 0, [recipient.publicKey]),
+        "ProposalNotFinalized"
+      );
+    });
+
+    it("freezes the actions once voting has started", async () => {
+      await castVote(treasuryProposalPda, filled(0x91), 0);
+      await castVote(treasuryProposalPda, filled(0x92), 0);
+      await castVote(treasuryProposalPda, filled(0x93), 1);
+      await addAction(lowTurnoutPda, 0, 0, transferSol(1000));
+      await castVote(lowTurnoutPda, filled(0x94), 0);
+
+      await expectError(
+        addAction(treasuryProposalPda, 2, 1, transferSol(1000)),
+        "VotingStarted"
+      );
+    });
+
+    it("rejects the losing option's action", async () => {
+      await waitUntil(votingEndsAt);
+      await finalize(treasuryProposalPda);
+
+      await expectError(
+        executeAction(treasuryProposalPda, 1, [recipient.publicKey]),
+        "ActionNotApproved"
+      );
+    });
+
+    it("rejects actions of proposals below the DAO's quorum", async () => {
+      await finalize(lowTurnoutPda);
+      const proposal = await program.account.proposal.fetch(lowTurnoutPda);
+      expect(proposal.outcome).to.deep.equal({ succeeded: {} });
+      // 1 ballot of 100 eligible voters, against a 20% quorum
+      expect(proposal.turnoutBps).to.equal(100);
+
+      await expectError(
+        executeAction(lowTurnoutPda, 0, [recipient.publicKey]),
+        "QuorumNotMet"
+      );
+    });
+
+    it("keeps the treasury locked while proof verification is disabled", async () => {
+      await expectError(
+        executeAction(treasuryProposalPda, 0, [recipient.publicKey]),
+        "VerificationDisabled"
+      );
+
+      const action = await program.account.proposalAction.fetch(
+        findActionPda(treasuryProposalPda, 0)
+      );
+      expect(action.executed).to.be.false;
+      expect(await provider.connection.getBalance(treasuryPda)).to.equal(
+        LAMPORTS_PER_SOL
+      );
+    });
+  });
+
+  describe("program upgrade action", () => {
+    const daoId = new BN(2);
+    const buffer = Keypair.generate();
+    let daoPda: PublicKey;
+    let treasuryPda: PublicKey;
+    let upgradeProposalPda: PublicKey;
+    let actionPda: PublicKey;
+    let programDataPda: PublicKey;
+
+    /** Instruction of the upgradeable loader; data starts with the u32 variant */
+    function loaderInstruction(
+      variant: number,
+      keys: { pubkey: PublicKey; isSigner: boolean; isWritable: boolean }[],
+      payload: Buffer = Buffer.alloc(0)
+    ): TransactionInstruction {
+      const data = Buffer.alloc(4);
+      data.writeUInt32LE(variant);
+      return new TransactionInstruction({
+        programId: BPF_LOADER_UPGRADEABLE_ID,
+        keys,
+        data: Buffer.concat([data, payload]),
+      });
+    }
+
+    function setAuthority(account: PublicKey, newAuthority: PublicKey) {
+      return loaderInstruction(4, [
+        { pubkey: account, isSigner: false, isWritable: true },
+        { pubkey: authority.publicKey, isSigner: true, isWritable: false },
+        { pubkey: newAuthority, isSigner: false, isWritable: false },
+      ]);
+    }
+
+    /** Upload the program's own binary into a fresh loader buffer */
+    async function writeBuffer(elf: Buffer) {
+      const space = 37 + elf.length; // Buffer metadata + program bytes
+      await provider.sendAndConfirm(
+        new Transaction().add(
+          SystemProgram.createAccount({
+            fromPubkey: authority.publicKey,
+            newAccountPubkey: buffer.publicKey,
+            lamports:
+              await provider.connection.getMinimumBalanceForRentExemption(space),
+            space,
+            programId: BPF_LOADER_UPGRADEABLE_ID,
+          }),
+          loaderInstruction(0, [
+            { pubkey: buffer.publicKey, isSigner: false, isWritable: true },
+            { pubkey: authority.publicKey, isSigner: false, isWritable: false },
+          ])
+        ),
+        [buffer]
+      );
+
+      const chunkSize = 900;
+      const writes: Promise<string>[] = [];
+      for (let offset = 0; offset < elf.length; offset += chunkSize) {
+        const chunk = elf.subarray(offset, offset + chunkSize);
+        const payload = Buffer.alloc(12);
+        payload.writeUInt32LE(offset, 0);
+        payload.writeUInt32LE(chunk.length, 4); // u64 length, high half zero
+        writes.push(
+          provider.sendAndConfirm(
+            new Transaction().add(
+              loaderInstruction(
+                1,
+                [
+                  { pubkey: buffer.publicKey, isSigner: false, isWritable: true },
+                  { pubkey: authority.publicKey, isSigner: true, isWritable: false },
+                ],
+                Buffer.concat([payload, chunk])
+              )
+            )
+          )
+        );
+        if (writes.length === 32) {
+          await Promise.all(writes.splice(0));
+        }
+      }
+      await Promise.all(writes);
+    }
+
+    function executeUpgrade() {
+      const meta = (pubkey: PublicKey, isWritable: boolean) => ({
+        pubkey,
+        isSigner: false,
+        isWritable,
+      });
+      return program.methods
+        .executeAction()
+        .accountsPartial({
+          proposal: upgradeProposalPda,
+          dao: daoPda,
+          treasury: treasuryPda,
+          action: actionPda,
+          prerequisite: null,
+          config: configPda,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+        })
+        .remainingAccounts([
+          meta(programDataPda, true),
+          meta(program.programId, true),
+          meta(buffer.publicKey, true),
+          meta(authority.publicKey, true),
+          meta(SYSVAR_RENT_PUBKEY, false),
+          meta(SYSVAR_CLOCK_PUBKEY, false),
+          meta(BPF_LOADER_UPGRADEABLE_ID, false),
+        ])
+        .rpc();
+    }
+
+    before(async () => {
+      [daoPda] = PublicKey.findProgramAddressSync(
+        [Buffer.from("dao"), daoId.toArrayLike(Buffer, "le", 8)],
+        program.programId
+      );
+      [treasuryPda] = PublicKey.findProgramAddressSync(
+        [Buffer.from("treasury"), daoPda.toBuffer()],
+        program.programId
+      );
+      [programDataPda] = PublicKey.findProgramAddressSync(
+        [program.programId.toBuffer()],
+        BPF_LOADER_UPGRADEABLE_ID
+      );
+
+      await program.methods
+        .createDao(daoId, "Upgrade DAO", 0)
+        .accountsPartial({
+          dao: daoPda,
+          treasury: treasuryPda,
+          authority: authority.publicKey,
+          config: configPda,
+          systemProgram: SystemProgram.programId,
+        })
+        .rpc();
+
+      // The DAO must own the buffer before the vote, so voters know exactly
+      // which bytes the action deploys
+      const elf = fs.readFileSync(
+        path.join(__dirname, "../target/deploy/voting_program.so")
+      );
+      await writeBuffer(elf);
+      await provider.sendAndConfirm(
+        new Transaction().add(setAuthority(buffer.publicKey, daoPda))
+      );
+
+      const votingEndsAt = now() + 4;
+      upgradeProposalPda = await createProposal(new BN(2100), {
+        votingEndsAt: new BN(votingEndsAt),
+        dao: daoPda,
+      });
+      [actionPda] = PublicKey.findProgramAddressSync(
+        [Buffer.from("action"), upgradeProposalPda.toBuffer(), Buffer.from([0])],
+        program.programId
+      );
+      await program.methods
+        .addProposalAction(0, {
+          upgradeProgram: {
+            program: program.programId,
+            buffer: buffer.publicKey,
+            spill: authority.publicKey,
+          },
+        })
+        .accountsPartial({
+          proposal: upgradeProposalPda,
+          action: actionPda,
+          authority: authority.publicKey,
+          config: configPda,
+          systemProgram: SystemProgram.programId,
+        })
+        .rpc();
+
+      await castVote(upgradeProposalPda, filled(0xa1), 0);
+      await waitUntil(votingEndsAt);
+      await finalize(upgradeProposalPda);
+    });
+
+    it("stores the upgrade action", async () => {
+      const action = await program.account.proposalAction.fetch(actionPda);
+      expect(action.action.upgradeProgram.program.toBase58()).to.equal(
+        program.programId.toBase58()
+      );
+      expect(action.action.upgradeProgram.buffer.toBase58()).to.equal(
+        buffer.publicKey.toBase58()
+      );
+    });
+
+    it("keeps the upgrade locked while proof verification is disabled", async () => {
+      // Even with the DAO holding the upgrade authority
+      await provider.sendAndConfirm(
+        new Transaction().add(setAuthority(programDataPda, daoPda))
+      );
+
+      await expectError(executeUpgrade(), "VerificationDisabled");
+
+      const action = await program.account.proposalAction.fetch(actionPda);
+      expect(action.executed).to.be.false;
+      expect(await provider.connection.getAccountInfo(buffer.publicKey)).to.not.be
+        .null;
+    });
+  });
+
+  describe("voter registry", () => {
+    const registryId = new BN(2);
+    let registryPda: PublicKey;
+
+    before(async () => {
+      registryPda = await createRegistry(registryId);
+    });
+
+    it("starts as an empty tree", async () => {
+      const registry = await program.account.voterRegistry.fetch(registryPda);
+      expect(registry.nextIndex.toNumber()).to.equal(0);
+      expect(registry.memberCount.toNumber()).to.equal(0);
+      expect(registry.root).to.deep.equal(fieldBytes(zeroHashes[20]));
+    });
+
+    it("appends commitments and recomputes the root on-chain", async () => {
+      // Voter leaves are hash(secret, secret)
+      const first = poseidonHash(11n, 11n);
+      const second = poseidonHash(12n, 12n);
+      await registerCommitment(registryPda, first);
+      await registerCommitment(registryPda, second);
+
+      const registry = await program.account.voterRegistry.fetch(registryPda);
+      expect(registry.nextIndex.toNumber()).to.equal(2);
+      expect(registry.memberCount.toNumber()).to.equal(2);
+      // Leaf 1's path: leaf 0, then empty subtrees
+      const siblings = [first, ...zeroHashes.slice(1, 20)];
+      expect(registry.root).to.deep.equal(fieldBytes(merkleRoot(second, 1, siblings)));
+    });
+
+    it("rejects commitments from anyone but the authority", async () => {
+      const stranger = Keypair.generate();
+      await expectError(
+        program.methods
+          .registerCommitment(fieldBytes(1n))
+          .accountsPartial({
+            registry: registryPda,
+            authority: stranger.publicKey,
+            config: configPda,
+          })
+          .signers([stranger])
+          .rpc(),
+        "Unauthorized"
+      );
+    });
+
+    it("rejects the zero commitment", async () => {
+      await expectError(registerCommitment(registryPda, 0n), "InvalidCommitment");
+    });
+
+    it("rejects proposals until the registry is frozen", async () => {
+      const registry = await program.account.voterRegistry.fetch(registryPda);
+      await expectError(
+        createProposal(new BN(1400), {
+          votersRoot: Buffer.from(registry.root),
+          registry: registryPda,
+        }),
+        "RegistryNotFrozen"
+      );
+    });
+
+    it("snapshots the frozen root into new proposals", async () => {
+      await freezeRegistry(registryPda);
+      await expectError(registerCommitment(registryPda, 3n), "RegistryNotOpen");
+      await expectError(freezeRegistry(registryPda), "InvalidRegistryStatus");
+
+      const registry = await program.account.voterRegistry.fetch(registryPda);
+      expect(registry.status).to.deep.equal({ frozen: {} });
+      await expectError(
+        createProposal(new BN(1401), { registry: registryPda }),
+        "RegistryRootMismatch"
+      );
+
+      const pda = await createProposal(new BN(1401), {
+        votersRoot: Buffer.from(registry.root),
+        registry: registryPda,
+      });
+      const proposal = await program.account.proposal.fetch(pda);
+      expect(proposal.registry.toBase58()).to.equal(registryPda.toBase58());
+      expect(proposal.votersRoot).to.deep.equal(registry.root);
+      expect(proposal.eligibleVoters.toNumber()).to.equal(2);
+    });
+
+    it("rejects an eligible voter count that differs from the registry", async () => {
+      const registry = await program.account.voterRegistry.fetch(registryPda);
+      await expectError(
+        createProposal(new BN(1402), {
+          votersRoot: Buffer.from(registry.root),
+          registry: registryPda,
+          eligibleVoters: new BN(3),
+        }),
+        "EligibleVotersMismatch"
+      );
+    });
+  });
+
+  describe("wallet registration", () => {
+    const voter = Keypair.generate();
+    const outsider = Keypair.generate();
+    let allowlistPda: PublicKey;
+    let authorityRegistryPda: PublicKey;
+
+    function findCommitmentPda(registry: PublicKey, wallet: PublicKey): PublicKey {
+      return PublicKey.findProgramAddressSync(
+        [Buffer.from("commitment"), registry.toBuffer(), wallet.toBuffer()],
+        program.programId
+      )[0];
+    }
+
+    function findAllowlistPda(registry: PublicKey, wallet: PublicKey): PublicKey {
+      return PublicKey.findProgramAddressSync(
+        [Buffer.from("allowlist"), registry.toBuffer(), wallet.toBuffer()],
+        program.programId
+      )[0];
+    }
+
+    function registerWallet(
+      registry: PublicKey,
+      wallet: Keypair,
+      commitment: bigint,
+      allowlisted = true
+    ) {
+      return program.methods
+        .registerWalletCommitment(fieldBytes(commitment), null)
+        .accountsPartial({
+          registry,
+          voterCommitment: findCommitmentPda(registry, wallet.publicKey),
+          allowlistEntry: allowlisted
+            ? findAllowlistPda(registry, wallet.publicKey)
+            : null,
+          wallet: wallet.publicKey,
+          config: configPda,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([wallet])
+        .rpc();
+    }
+
+    before(async () => {
+      for (const wallet of [voter, outsider]) {
+        await provider.connection.confirmTransaction(
+          await provider.connection.requestAirdrop(wallet.publicKey, LAMPORTS_PER_SOL)
+        );
+      }
+      allowlistPda = await createRegistry(new BN(10), { allowlist: {} });
+      authorityRegistryPda = await createRegistry(new BN(11));
+
+      await program.methods
+        .addToAllowlist(voter.publicKey)
+        .accountsPartial({
+          registry: allowlistPda,
+          allowlistEntry: findAllowlistPda(allowlistPda, voter.publicKey),
+          authority: authority.publicKey,
+          config: configPda,
+          systemProgram: SystemProgram.programId,
+        })
+        .rpc();
+    });
+
+    it("rejects wallets that are not allowlisted", async () => {
+      await expectError(
+        registerWallet(allowlistPda, outsider, 21n, false),
+        "NotEligible"
+      );
+    });
+
+    it("stores an allowlisted wallet's commitment at its leaf index", async () => {
+      const commitment = poseidonHash(22n, 22n);
+      await registerWallet(allowlistPda, voter, commitment);
+
+      const entry = await program.account.voterCommitment.fetch(
+        findCommitmentPda(allowlistPda, voter.publicKey)
+      );
+      expect(entry.wallet.toBase58()).to.equal(voter.publicKey.toBase58());
+      expect(entry.commitment).to.deep.equal(fieldBytes(commitment));
+      expect(entry.leafIndex.toNumber()).to.equal(0);
+
+      const registry = await program.account.voterRegistry.fetch(allowlistPda);
+      expect(registry.root).to.deep.equal(
+        fieldBytes(merkleRoot(commitment, 0, zeroHashes.slice(0, 20)))
+      );
+    });
+
+    it("lets each wallet register only once", async () => {
+      await expectError(
+        registerWallet(allowlistPda, voter, poseidonHash(23n, 23n)),
+        "already in use"
+      );
+    });
+
+    it("keeps wallet-bound registries closed to the authority", async () => {
+      await expectError(
+        registerCommitment(allowlistPda, poseidonHash(24n, 24n)),
+        "WalletRegistrationOnly"
+      );
+      await expectError(
+        registerWallet(authorityRegistryPda, voter, poseidonHash(25n, 25n), false),
+        "WalletRegistrationDisabled"
+      );
+    });
+  });
+
+  describe("recent registry roots", () => {
+    let registryPda: PublicKey;
+    let proposalPda: PublicKey;
+    let olderRoot: number[];
+    let snapshotRoot: number[];
+
+    before(async () => {
+      registryPda = await createRegistry(new BN(4));
+      await registerCommitment(registryPda, poseidonHash(31n, 31n));
+      olderRoot = (await program.account.voterRegistry.fetch(registryPda)).root;
+      await registerCommitment(registryPda, poseidonHash(32n, 32n));
+      snapshotRoot = (await program.account.voterRegistry.fetch(registryPda)).root;
+    });
+
+    it("requires a registry for accept_recent_roots", async () => {
+      await expectError(
+        createProposal(new BN(1602), { acceptRecentRoots: true }),
+        "RegistryRequired"
+      );
+    });
+
+    it("snapshots the registry's leaf count", async () => {
+      await expectError(
+        createProposal(new BN(1600), {
+          votersRoot: Buffer.from(snapshotRoot),
+          registry: registryPda,
+        }),
+        "RegistryNotFrozen"
+      );
+
+      // accept_recent_roots proposals can use the registry while it is open
+      proposalPda = await createProposal(new BN(1600), {
+        votersRoot: Buffer.from(snapshotRoot),
+        registry: registryPda,
+        acceptRecentRoots: true,
+      });
+      const proposal = await program.account.proposal.fetch(proposalPda);
+      expect(proposal.snapshotLeafCount.toNumber()).to.equal(2);
+      expect(proposal.acceptRecentRoots).to.be.true;
+    });
+
+    it("accepts ballots against a root from before the snapshot", async () => {
+      await castVote(proposalPda, filled(0x91), 0, {
+        votersRoot: olderRoot,
+        registry: registryPda,
+      });
+      const proposal = await program.account.proposal.fetch(proposalPda);
+      expect(proposal.voteCounts[0].toNumber()).to.equal(1);
+    });
+
+    it("rejects roots produced after the snapshot", async () => {
+      await registerCommitment(registryPda, poseidonHash(33n, 33n));
+      const laterRoot = (await program.account.voterRegistry.fetch(registryPda)).root;
+
+      await expectError(
+        castVote(proposalPda, filled(0x92), 0, {
+          votersRoot: laterRoot,
+          registry: registryPda,
+        }),
+        "RootAfterSnapshot"
+      );
+      await expectError(
+        castVote(proposalPda, filled(0x93), 0, {
+          votersRoot: Array.from(filled(0x5e)),
+          registry: registryPda,
+        }),
+        "RootNotInHistory"
+      );
+    });
+
+    it("rejects archived registries even with accept_recent_roots", async () => {
+      await freezeRegistry(registryPda);
+      await program.methods
+        .archiveRegistry()
+        .accountsPartial({ registry: registryPda, authority: authority.publicKey })
+        .rpc();
+      const registry = await program.account.voterRegistry.fetch(registryPda);
+      await expectError(
+        createProposal(new BN(1601), {
+          votersRoot: Buffer.from(registry.root),
+          registry: registryPda,
+          acceptRecentRoots: true,
+        }),
+        "RegistryArchived"
+      );
+    });
+  });
+
+  describe("commitment revocation", () => {
+    const secrets = [21n, 22n];
+    // Revoked slots hold the field element 1, empty ones 0
+    const revokedMarker = 1n;
+    let registryPda: PublicKey;
+
+    /** Voter leaf hash(secret, secret) of the i-th member */
+    function leaf(i: number): bigint {
+      return poseidonHash(secrets[i], secrets[i]);
+    }
+
+    function revoke(leafIndex: number, siblings: bigint[]) {
+      return program.methods
+        .revokeCommitment(new BN(leafIndex), siblings.map(fieldBytes))
+        .accountsPartial({ registry: registryPda, authority: authority.publicKey })
+        .rpc();
+    }
+
+    before(async () => {
+      registryPda = await createRegistry(new BN(3));
+      await registerCommitment(registryPda, leaf(0));
+      await registerCommitment(registryPda, leaf(1));
+      await freezeRegistry(registryPda);
+    });
+
+    it("marks the revoked slot in the revocation tree", async () => {
+      // The revocation tree is still empty, so the path is all zero subtrees
+      await revoke(1, zeroHashes.slice(0, 20));
+
+      const registry = await program.account.voterRegistry.fetch(registryPda);
+      expect(registry.memberCount.toNumber()).to.equal(1);
+      expect(registry.revokedCount.toNumber()).to.equal(1);
+      expect(registry.nextIndex.toNumber()).to.equal(2);
+      expect(registry.revocationRoot).to.deep.equal(
+        fieldBytes(merkleRoot(revokedMarker, 1, zeroHashes.slice(0, 20)))
+      );
+    });
+
+    it("rejects revoking the same slot twice", async () => {
+      await expectError(revoke(1, zeroHashes.slice(0, 20)), "RevocationPathMismatch");
+    });
+
+    it("rejects leaves that were never registered", async () => {
+      await expectError(revoke(2, zeroHashes.slice(0, 20)), "LeafNotRegistered");
+    });
+
+    it("rejects revocations by anyone but the authority", async () => {
+      const stranger = Keypair.generate();
+      await expectError(
+        program.methods
+          .revokeCommitment(new BN(0), zeroHashes.slice(0, 20).map(fieldBytes))
+          .accountsPartial({ registry: registryPda, authority: stranger.publicKey })
+          .signers([stranger])
+          .rpc(),
+        "Unauthorized"
+      );
+    });
+
+    it("copies the revocation root into new proposals", async () => {
+      const registry = await program.account.voterRegistry.fetch(registryPda);
+      const pda = await createProposal(new BN(1500), {
+        votersRoot: Buffer.from(registry.root),
+        registry: registryPda,
+      });
+
+      const proposal = await program.account.proposal.fetch(pda);
+      expect(proposal.revocationRoot).to.deep.equal(registry.revocationRoot);
+      // Revoked members no longer count as eligible
+      expect(proposal.eligibleVoters.toNumber()).to.equal(1);
+    });
+
+    it("revokes a second slot next to the first", async () => {
+      // Slot 0's sibling is the revoked slot 1
+      const siblings = [revokedMarker, ...zeroHashes.slice(1, 20)];
+      await revoke(0, siblings);
+
+      const registry = await program.account.voterRegistry.fetch(registryPda);
+      expect(registry.memberCount.toNumber()).to.equal(0);
+      expect(registry.revokedCount.toNumber()).to.equal(2);
+      expect(registry.revocationRoot).to.deep.equal(
+        fieldBytes(merkleRoot(revokedMarker, 0, siblings))
+      );
+    });
+
+    it("rejects revocations once the registry is archived", async () => {
+      await program.methods
+        .archiveRegistry()
+        .accountsPartial({ registry: registryPda, authority: authority.publicKey })
+        .rpc();
+      await expectError(revoke(1, zeroHashes.slice(0, 20)), "RegistryArchived");
+    });
+  });
+
+  describe("voter groups", () => {
+    const communityRoot = filled(0xc0);
+    // A council of 2 at weight 3 and a community of 8 at weight 1
+    const groups = [
+      { votersRoot: Array.from(votersRoot), weight: 3, eligibleVoters: new BN(2) },
+      { votersRoot: Array.from(communityRoot), weight: 1, eligibleVoters: new BN(8) },
+    ];
+    let groupsPda: PublicKey;
+    let votingEndsAt: number;
+
+    before(async () => {
+      votingEndsAt = now() + 4;
+      groupsPda = await createProposal(new BN(1700), {
+        votingEndsAt: new BN(votingEndsAt),
+        eligibleVoters: null,
+        voterGroups: groups,
+      });
+    });
+
+    it("rejects a first group that differs from voters_root", async () => {
+      await expectError(
+        createProposal(new BN(1701), {
+          voterGroups: [groups[1], groups[0]],
+          eligibleVoters: null,
+        }),
+        "VoterGroupRootMismatch"
+      );
+    });
+
+    it("rejects groups without weight", async () => {
+      await expectError(
+        createProposal(new BN(1701), {
+          voterGroups: [{ ...groups[0], weight: 0 }],
+          eligibleVoters: null,
+        }),
+        "InvalidGroupWeight"
+      );
+    });
+
+    it("rejects an eligible voter count other than the groups' total", async () => {
+      await expectError(
+        createProposal(new BN(1701), { voterGroups: groups, eligibleVoters: new BN(9) }),
+        "EligibleVotersMismatch"
+      );
+    });
+
+    it("tallies each group separately", async () => {
+      await castVote(groupsPda, filled(0xd1), 0);
+      await castVote(groupsPda, filled(0xd2), 1, { group: 1 });
+      await castVote(groupsPda, filled(0xd3), 1, { group: 1 });
+
+      const proposal = await program.account.proposal.fetch(groupsPda);
+      expect(proposal.eligibleVoters.toNumber()).to.equal(10);
+      expect(proposal.voterGroups[0].voteCounts[0].toNumber()).to.equal(1);
+      expect(proposal.voterGroups[1].voteCounts[1].toNumber()).to.equal(2);
+      expect(proposal.voteCounts.every((count) => count.toNumber() === 0)).to.be.true;
+    });
+
+    it("scopes nullifiers to their group", async () => {
+      // The same nullifier is spent once per group
+      await castVote(groupsPda, filled(0xd4), 0, { group: 1 });
+      await castVote(groupsPda, filled(0xd4), 0);
+      await expectError(
+        castVote(groupsPda, filled(0xd4), 0, { group: 1 }),
+        "already in use"
+      );
+    });
+
+    it("rejects groups the proposal doesn't have and foreign roots", async () => {
+      await expectError(
+        castVote(groupsPda, filled(0xd5), 0, { group: 2 }),
+        "InvalidVoterGroup"
+      );
+      await expectError(
+        castVote(groupsPda, filled(0xd6), 0, {
+          group: 1,
+          votersRoot: Array.from(votersRoot),
+        }),
+        "RootNotAccepted"
+      );
+    });
+
+    it("combines the group tallies by weight", async () => {
+      await waitUntil(votingEndsAt);
+      await finalize(groupsPda);
+
+      const proposal = await program.account.proposal.fetch(groupsPda);
+      // Option 0: 2 council ballots * 3 + 1 community ballot; option 1: 2 community ballots
+      expect(proposal.voteCounts[0].toNumber()).to.equal(7);
+      expect(proposal.voteCounts[1].toNumber()).to.equal(2);
+      expect(proposal.winningOption).to.equal(0);
+      // 5 ballots of 10 eligible voters
+      expect(proposal.totalVotes.toNumber()).to.equal(5);
+      expect(proposal.turnoutBps).to.equal(5000);
+    });
+  });
+
+  describe("weighted voting", () => {
+    let weightedPda: PublicKey;
+
+    before(async () => {
+      weightedPda = await createProposal(new BN(1800), { weighted: true });
+    });
+
+    it("rejects weighted registries without balances", async () => {
+      await expectError(
+        createRegistry(new BN(12), { allowlist: {} }, true),
+        "WeightNotSupported"
+      );
+    });
+
+    it("counts a ballot with its exact weight", async () => {
+      await castVote(weightedPda, filled(0xa5), 1, { weight: new BN(250) });
+      const proposal = await program.account.proposal.fetch(weightedPda);
+      expect(proposal.voteCounts[1].toNumber()).to.equal(250);
+      expect(proposal.totalVotes.toNumber()).to.equal(1);
+    });
+
+    it("rejects unweighted ballots on weighted proposals", async () => {
+      await expectError(castVote(weightedPda, filled(0xa7), 0), "InvalidWeight");
+    });
+  });
+
+  describe("approval voting", () => {
+    let approvalPda: PublicKey;
+    let votingEndsAt: number;
+
+    before(async () => {
+      votingEndsAt = now() + 4;
+      approvalPda = await createProposal(new BN(1900), {
+        numOptions: 4,
+        ballotMode: { approval: {} },
+        votingEndsAt: new BN(votingEndsAt),
+      });
+    });
+
+    it("counts every approved option", async () => {
+      await castVote(approvalPda, filled(0xe1), 0b0101);
+      await castVote(approvalPda, filled(0xe2), 0b0110);
+
+      const proposal = await program.account.proposal.fetch(approvalPda);
+      expect(proposal.voteCounts.slice(0, 4).map((count) => count.toNumber())).to.deep.equal([
+        1, 1, 2, 0,
+      ]);
+      expect(proposal.totalVotes.toNumber()).to.equal(2);
+    });
+
+    it("rejects empty masks and options beyond num_options", async () => {
+      await expectError(castVote(approvalPda, filled(0xe3), 0), "InvalidApprovalMask");
+      await expectError(
+        castVote(approvalPda, filled(0xe4), 0b10001),
+        "InvalidApprovalMask"
+      );
+    });
+
+    it("picks the most approved option as the winner", async () => {
+      await waitUntil(votingEndsAt);
+      await finalize(approvalPda);
+
+      const proposal = await program.account.proposal.fetch(approvalPda);
+      expect(proposal.winningOption).to.equal(2);
+    });
+  });
+
+  describe("ranked-choice voting", () => {
+    let rankedPda: PublicKey;
+    let votingEndsAt: number;
+    // First preferences tie A and B 2-2; C's ballot transfers to B
+    const rankings = [[0], [0, 1], [1], [1, 0], [2, 1]];
+    const nullifiers = rankings.map((_, i) => filled(0xc0 + i));
+
+    function findBallotPda(nullifier: Buffer): PublicKey {
+      return PublicKey.findProgramAddressSync(
+        [Buffer.from("ballot"), rankedPda.toBuffer(), nullifier],
+        program.programId
+      )[0];
+    }
+
+    function tabulate() {
+      return program.methods
+        .tabulateIrv()
+        .accountsPartial({ proposal: rankedPda })
+        .remainingAccounts(
+          nullifiers.map((nullifier) => ({
+            pubkey: findBallotPda(nullifier),
+            isSigner: false,
+            isWritable: true,
+          }))
+        )
+        .rpc();
+    }
+
+    before(async () => {
+      votingEndsAt = now() + 4;
+      rankedPda = await createProposal(new BN(2100), {
+        votingEndsAt: new BN(votingEndsAt),
+        numOptions: 3,
+        ballotMode: { ranked: {} },
+      });
+      for (let i = 0; i < rankings.length; i++) {
+        await program.methods
+          .castRankedVote(
+            Array.from(nullifiers[i]),
+            Buffer.from(rankings[i]),
+            mockProof,
+            null,
+            new BN(0)
+          )
+          .accountsPartial({
+            proposal: rankedPda,
+            nullifierAccount: findNullifierPda(rankedPda, nullifiers[i]),
+            ballot: findBallotPda(nullifiers[i]),
+            prerequisite: null,
+            registry: null,
+            payer: authority.publicKey,
+            config: configPda,
+            systemProgram: SystemProgram.programId,
+          })
+          .rpc();
+      }
+    });
+
+    it("keeps a running first-preference tally", async () => {
+      const proposal = await program.account.proposal.fetch(rankedPda);
+      expect(proposal.voteCounts.slice(0, 3).map((count) => count.toNumber())).to.deep.equal([
+        2, 2, 1,
+      ]);
+    });
+
+    it("rejects single-choice ballots", async () => {
+      await expectError(castVote(rankedPda, filled(0xcf), 0), "RankedBallotRequired");
+    });
+
+    it("does not tabulate before voting ends", async () => {
+      await expectError(tabulate(), "VotingNotEnded");
+    });
+
+    it("eliminates the last-placed option and transfers its ballots", async () => {
+      await waitUntil(votingEndsAt);
+      await expectError(finalize(rankedPda), "TabulationIncomplete");
+
+      // Round 0: no majority, C is eliminated
+      await tabulate();
+      let proposal = await program.account.proposal.fetch(rankedPda);
+      expect(proposal.irv.complete).to.be.false;
+      expect(proposal.irv.eliminated).to.equal(0b100);
+
+      // Round 1: C's ballot moves to B, which wins 3-2
+      await tabulate();
+      proposal = await program.account.proposal.fetch(rankedPda);
+      expect(proposal.irv.complete).to.be.true;
+      expect(
+        proposal.irv.rounds.map((round) => round.slice(0, 3).map((count) => count.toNumber()))
+      ).to.deep.equal([
+        [2, 2, 1],
+        [2, 3, 0],
+      ]);
+
+      await expectError(tabulate(), "TabulationComplete");
+
+      await finalize(rankedPda);
+      proposal = await program.account.proposal.fetch(rankedPda);
+      expect(proposal.outcome).to.deep.equal({ succeeded: {} });
+      expect(proposal.winningOption).to.equal(1);
+    });
+  });
+
+  describe("quadratic voting", () => {
+    let quadraticPda: PublicKey;
+
+    before(async () => {
+      quadraticPda = await createProposal(new BN(2200), {
+        numOptions: 3,
+        weighted: true,
+        ballotMode: { quadratic: {} },
+      });
+    });
+
+    it("requires weighted leaves for the credit budgets", async () => {
+      await expectError(
+        createProposal(new BN(2201), { ballotMode: { quadratic: {} } }),
+        "CreditBudgetRequired"
+      );
+    });
+
+    it("adds the vote vector to the tally", async () => {
+      // 3 votes for option 0 and 4 for option 2, 25 credits in-circuit
+      await castVectorVote(quadraticPda, filled(0xb1), [3, 0, 4, 0, 0, 0, 0, 0]);
+
+      const proposal = await program.account.proposal.fetch(quadraticPda);
+      expect(proposal.voteCounts.slice(0, 3).map((count) => count.toNumber())).to.deep.equal([
+        3, 0, 4,
+      ]);
+      expect(proposal.totalVotes.toNumber()).to.equal(1);
+    });
+
+    it("rejects empty vectors and votes for non-existent options", async () => {
+      await expectError(
+        castVectorVote(quadraticPda, filled(0xb2), [0, 0, 0, 0, 0, 0, 0, 0]),
+        "InvalidVoteVector"
+      );
+      await expectError(
+        castVectorVote(quadraticPda, filled(0xb3), [1, 0, 0, 1, 0, 0, 0, 0]),
+        "InvalidVoteVector"
+      );
+    });
+
+    it("rejects single-choice ballots", async () => {
+      await expectError(castVote(quadraticPda, filled(0xb4), 0), "VectorBallotRequired");
+    });
+  });
+
+  describe("points voting", () => {
+    let pointsPda: PublicKey;
+    const points = { points: { budget: 100 } };
+
+    before(async () => {
+      pointsPda = await createProposal(new BN(2300), { numOptions: 3, ballotMode: points });
+    });
+
+    it("rejects an empty point budget", async () => {
+      await expectError(
+        createProposal(new BN(2301), { ballotMode: { points: { budget: 0 } } }),
+        "InvalidPointBudget"
+      );
+    });
+
+    it("adds each ballot's points to the option totals", async () => {
+      await castVectorVote(pointsPda, filled(0xb5), [60, 25, 15, 0, 0, 0, 0, 0]);
+      await castVectorVote(pointsPda, filled(0xb6), [0, 100, 0, 0, 0, 0, 0, 0]);
+
+      const proposal = await program.account.proposal.fetch(pointsPda);
+      expect(proposal.voteCounts.slice(0, 3).map((count) => count.toNumber())).to.deep.equal([
+        60, 125, 15,
+      ]);
+      expect(proposal.totalVotes.toNumber()).to.equal(2);
+    });
+
+    it("rejects ballots that don't spend the exact budget", async () => {
+      await expectError(
+        castVectorVote(pointsPda, filled(0xb7), [60, 25, 0, 0, 0, 0, 0, 0]),
+        "PointBudgetMismatch"
+      );
+    });
+
+    it("multiplies points by the weight on weighted proposals", async () => {
+      const weightedPointsPda = await createProposal(new BN(2302), {
+        numOptions: 3,
+        ballotMode: points,
+        weighted: true,
+      });
+      await castVectorVote(weightedPointsPda, filled(0xb8), [50, 0, 50, 0, 0, 0, 0, 0], {
+        weight: new BN(3),
+      });
+
+      const proposal = await program.account.proposal.fetch(weightedPointsPda);
+      expect(proposal.voteCounts.slice(0, 3).map((count) => count.toNumber())).to.deep.equal([
+        150, 0, 150,
+      ]);
+    });
+  });
+
+  describe("encrypted ballots", () => {
+    let encryptedPda: PublicKey;
+    let tallyPda: PublicKey;
+    let votingEndsAt: number;
+    const secret = randomScalar();
+    const key = g1Mul(G, secret);
+    // Option 2 wins 2-1; nobody picks option 1
+    const choices = [0, 2, 2];
+    const nullifiers = choices.map((_, i) => filled(0xd0 + i));
+    const abandoned = filled(0xdf);
+
+    // Ballot helpers default to the proposal above; the committee tests pass theirs
+    function findEncryptedBallotPda(nullifier: Buffer, proposal = encryptedPda): PublicKey {
+      return PublicKey.findProgramAddressSync(
+        [
+          Buffer.from("encrypted_ballot"),
+          proposal.toBuffer(),
+          nullifier,
+          authority.publicKey.toBuffer(),
+        ],
+        program.programId
+      )[0];
+    }
+
+    function openBallot(nullifier: Buffer, proposal = encryptedPda) {
+      return program.methods
+        .openEncryptedBallot(Array.from(nullifier))
+        .accountsPartial({
+          proposal,
+          ballot: findEncryptedBallotPda(nullifier, proposal),
+          payer: authority.publicKey,
+          config: configPda,
+          systemProgram: SystemProgram.programId,
+        })
+        .rpc();
+    }
+
+    /** Open a ballot buffer and post one ciphertext per option; returns the summed randomness */
+    async function postBallot(
+      nullifier: Buffer,
+      bits: number[],
+      proposal = encryptedPda,
+      ballotKey = key
+    ): Promise<bigint> {
+      const ballot = findEncryptedBallotPda(nullifier, proposal);
+      await openBallot(nullifier, proposal);
+
+      const encrypted = bits.map((bit, option) =>
+        encryptBit(ballotKey, bit, [
+          Buffer.from("bit"),
+          proposal.toBuffer(),
+          nullifier,
+          Buffer.from([option]),
+        ])
+      );
+      // Two options per transaction keeps each one under the size limit
+      for (let first = 0; first < bits.length; first += 2) {
+        await program.methods
+          .postEncryptedOptions(
+            Array.from(nullifier),
+            first,
+            encrypted.slice(first, first + 2).map((entry) => entry.option)
+          )
+          .accountsPartial({ proposal, ballot, payer: authority.publicKey })
+          .rpc();
+      }
+      return encrypted.reduce((sum, entry) => (sum + entry.r) % SCALAR_ORDER, 0n);
+    }
+
+    function castEncrypted(
+      nullifier: Buffer,
+      randomness: bigint,
+      proposal = encryptedPda,
+      ballotKey = key
+    ) {
+      const sumProof = proveDleq(G, ballotKey, randomness, [
+        Buffer.from("one-hot"),
+        proposal.toBuffer(),
+        nullifier,
+      ]);
+      return program.methods
+        .castEncryptedVote(Array.from(nullifier), sumProof, mockProof, null)
+        .accountsPartial({
+          proposal,
+          nullifierAccount: findNullifierPda(proposal, nullifier),
+          ballot: findEncryptedBallotPda(nullifier, proposal),
+          encryptedTally: findEncryptedTallyPda(proposal),
+          prerequisite: null,
+          registry: null,
+          payer: authority.publicKey,
+          config: configPda,
+          systemProgram: SystemProgram.programId,
+        })
+        .rpc();
+    }
+
+    function closeBallot(nullifier: Buffer) {
+      return program.methods
+        .closeEncryptedBallot(Array.from(nullifier))
+        .accountsPartial({
+          proposal: encryptedPda,
+          ballot: findEncryptedBallotPda(nullifier),
+          payer: authority.publicKey,
+        })
+        .rpc();
+    }
+
+    /** decrypt_tally with a proof per option against the on-chain tally */
+    async function decryptTally(totals: number[]) {
+      const tally = await program.account.encryptedTally.fetch(tallyPda);
+      const proofs = totals.map((_, option) => {
+        const c1 = pointFromBytes(Buffer.from(tally.ciphertexts[option]));
+        return proveDleq(G, c1, secret, [
+          Buffer.from("decrypt"),
+          encryptedPda.toBuffer(),
+          Buffer.from([option]),
+        ]);
+      });
+      return program.methods
+        .decryptTally(
+          totals.map((total) => new BN(total)),
+          proofs
+        )
+        .accountsPartial({ proposal: encryptedPda, encryptedTally: tallyPda })
+        .rpc();
+    }
+
+    before(async () => {
+      // Room for 16 transactions before the deadline
+      votingEndsAt = now() + 25;
+      encryptedPda = await createProposal(new BN(2400), {
+        votingEndsAt: new BN(votingEndsAt),
+        numOptions: 3,
+        ballotMode: { encrypted: { key: Array.from(pointBytes(key)) } },
+      });
+      tallyPda = findEncryptedTallyPda(encryptedPda);
+      for (let i = 0; i < choices.length; i++) {
+        const bits = [0, 1, 2].map((option) => (option === choices[i] ? 1 : 0));
+        await castEncrypted(nullifiers[i], await postBallot(nullifiers[i], bits));
+      }
+    });
+
+    it("keeps the tally in its own account, only for encrypted proposals", async () => {
+      const tally = await program.account.encryptedTally.fetch(tallyPda);
+      expect(tally.proposal.toBase58()).to.equal(encryptedPda.toBase58());
+      expect(tally.decrypted).to.be.false;
+
+      await expectError(
+        createProposal(new BN(2401), {
+          encryptedTally: findEncryptedTallyPda(findProposalPda(new BN(2401))),
+        }),
+        "EncryptedTallyMismatch"
+      );
+      await expectError(
+        createProposal(new BN(2402), {
+          ballotMode: { encrypted: { key: Array.from(pointBytes(key)) } },
+          encryptedTally: null,
+        }),
+        "EncryptedTallyMismatch"
+      );
+    });
+
+    it("adds cast ballots into the tally and closes their buffers", async () => {
+      for (const nullifier of nullifiers) {
+        expect(
+          await provider.connection.getAccountInfo(findEncryptedBallotPda(nullifier))
+        ).to.be.null;
+      }
+      const proposal = await program.account.proposal.fetch(encryptedPda);
+      expect(proposal.totalVotes.toNumber()).to.equal(3);
+      expect(proposal.voteCounts.slice(0, 3).map((count) => count.toNumber())).to.deep.equal([
+        0, 0, 0,
+      ]);
+    });
+
+    it("rejects a ballot that doesn't select exactly one option", async () => {
+      const randomness = await postBallot(abandoned, [0, 0, 0]);
+      await expectError(castEncrypted(abandoned, randomness), "InvalidBallotProof");
+    });
+
+    it("keeps an uncast buffer until voting ends", async () => {
+      await expectError(closeBallot(abandoned), "VotingNotEnded");
+    });
+
+    it("decrypts the tally before finalizing", async () => {
+      await waitUntil(votingEndsAt);
+      await expectError(finalize(encryptedPda, tallyPda), "TallyNotDecrypted");
+      await expectError(decryptTally([1, 1, 1]), "InvalidDecryptionProof");
+
+      await decryptTally([1, 0, 2]);
+      await expectError(decryptTally([1, 0, 2]), "TallyAlreadyDecrypted");
+
+      await expectError(finalize(encryptedPda), "EncryptedTallyMismatch");
+      await finalize(encryptedPda, tallyPda);
+      const proposal = await program.account.proposal.fetch(encryptedPda);
+      expect(proposal.voteCounts.slice(0, 3).map((count) => count.toNumber())).to.deep.equal([
+        1, 0, 2,
+      ]);
+      expect(proposal.outcome).to.deep.equal({ succeeded: {} });
+      expect(proposal.winningOption).to.equal(2);
+    });
+
+    it("lets the payer close an abandoned buffer after voting ends", async () => {
+      const ballot = findEncryptedBallotPda(abandoned);
+      const rent = (await provider.connection.getAccountInfo(ballot)).lamports;
+      const balance = await provider.connection.getBalance(authority.publicKey);
+
+      await closeBallot(abandoned);
+      expect(await provider.connection.getAccountInfo(ballot)).to.be.null;
+      // The rent is refunded, less the transaction fee
+      expect(await provider.connection.getBalance(authority.publicKey)).to.be.greaterThan(
+        balance + rent - 10_000
+      );
+    });
+
+    describe("decryption committee", () => {
+      let committeeProposal: PublicKey;
+      let committeePda: PublicKey;
+      let committeeEndsAt: number;
+      const members = [0, 1, 2].map(() => Keypair.generate());
+      // Each member deals a random degree-1 polynomial (2-of-3)
+      const polynomials = members.map(() => [randomScalar(), randomScalar()]);
+      // Dealer 1 leaves a complaint unanswered and is disqualified
+      const qualified = [0, 2];
+      const tallyKey = qualified.reduce<Point>(
+        (sum, dealer) => g1Add(sum, g1Mul(G, polynomials[dealer][0])),
+        null
+      );
+      const nullifier = filled(0xe0);
+
+      /** Member j's share of the tally secret: sum of the qualified f_i(j + 1) */
+      function shareSecret(member: number): bigint {
+        return qualified.reduce(
+          (sum, dealer) => (sum + evaluate(dealer, member)) % SCALAR_ORDER,
+          0n
+        );
+      }
+
+      /** Dealer i's f_i(j + 1), sent privately to member j */
+      function evaluate(dealer: number, member: number): bigint {
+        const [a0, a1] = polynomials[dealer];
+        return (a0 + a1 * BigInt(member + 1)) % SCALAR_ORDER;
+      }
+
+      function commitments(dealer: number): number[][] {
+        return polynomials[dealer].map((coefficient) =>
+          Array.from(pointBytes(g1Mul(G, coefficient)))
+        );
+      }
+
+      function setCommittee(wallets: PublicKey[]) {
+        return program.methods
+          .setDecryptionCommittee(wallets, 2, new BN(6))
+          .accountsPartial({
+            proposal: committeeProposal,
+            committee: committeePda,
+            authority: authority.publicKey,
+            config: configPda,
+            systemProgram: SystemProgram.programId,
+          })
+          .rpc();
+      }
+
+      function deal(member: number, signer = members[member]) {
+        const proof = proveDleq(G, G, polynomials[member][0], [
+          Buffer.from("dealing"),
+          committeeProposal.toBuffer(),
+          Buffer.from([member]),
+        ]);
+        return program.methods
+          .submitCommitteeDealing(member, commitments(member), proof)
+          .accountsPartial({
+            proposal: committeeProposal,
+            committee: committeePda,
+            member: signer.publicKey,
+            config: configPda,
+          })
+          .signers([signer])
+          .rpc();
+      }
+
+      function complain(member: number, dealer: number) {
+        return program.methods
+          .fileComplaint(member, dealer)
+          .accountsPartial({
+            proposal: committeeProposal,
+            committee: committeePda,
+            member: members[member].publicKey,
+            config: configPda,
+          })
+          .signers([members[member]])
+          .rpc();
+      }
+
+      function answer(dealer: number, member: number, share = evaluate(dealer, member)) {
+        return program.methods
+          .answerComplaint(dealer, member, Array.from(fieldBytes(share)), commitments(dealer))
+          .accountsPartial({
+            proposal: committeeProposal,
+            committee: committeePda,
+            dealer: members[dealer].publicKey,
+            config: configPda,
+          })
+          .signers([members[dealer]])
+          .rpc();
+      }
+
+      function disqualify(dealer: number) {
+        return program.methods
+          .disqualifyDealer(dealer, commitments(dealer))
+          .accountsPartial({ proposal: committeeProposal, committee: committeePda })
+          .rpc();
+      }
+
+      function fixKey() {
+        return program.methods
+          .fixCommitteeKey()
+          .accountsPartial({ proposal: committeeProposal, committee: committeePda })
+          .rpc();
+      }
+
+      async function submitShares(member: number) {
+        const tally = await program.account.encryptedTally.fetch(
+          findEncryptedTallyPda(committeeProposal)
+        );
+        const secretShare = shareSecret(member);
+        const shares = [0, 1].map((option) => {
+          const c1 = pointFromBytes(Buffer.from(tally.ciphertexts[option]));
+          return {
+            share: Array.from(pointBytes(g1Mul(c1, secretShare))),
+            proof: proveDleq(G, c1, secretShare, [
+              Buffer.from("share"),
+              committeeProposal.toBuffer(),
+              Buffer.from([member]),
+              Buffer.from([option]),
+            ]),
+          };
+        });
+        return program.methods
+          .submitDecryptionShare(member, 0, shares)
+          .accountsPartial({
+            proposal: committeeProposal,
+            encryptedTally: findEncryptedTallyPda(committeeProposal),
+            committee: committeePda,
+          })
+          .rpc();
+      }
+
+      function combine(totals: number[]) {
+        return program.methods
+          .combineDecryptionShares(totals.map((total) => new BN(total)))
+          .accountsPartial({
+            proposal: committeeProposal,
+            encryptedTally: findEncryptedTallyPda(committeeProposal),
+            committee: committeePda,
+          })
+          .rpc();
+      }
+
+      before(async () => {
+        // Three 6s key generation rounds, then time to vote
+        committeeEndsAt = now() + 30;
+        committeeProposal = await createProposal(new BN(2403), {
+          votingEndsAt: new BN(committeeEndsAt),
+          ballotMode: { encrypted: { key: Array(64).fill(0) } },
+        });
+        committeePda = PublicKey.findProgramAddressSync(
+          [Buffer.from("committee"), committeeProposal.toBuffer()],
+          program.programId
+        )[0];
+      });
+
+      it("rejects a committee listing a member twice", async () => {
+        await expectError(
+          setCommittee([members[0].publicKey, members[1].publicKey, members[0].publicKey]),
+          "DuplicateCommitteeMember"
+        );
+        await setCommittee(members.map((member) => member.publicKey));
+      });
+
+      it("holds voting until the tally key is fixed", async () => {
+        await deal(0);
+        await deal(1);
+        await deal(2);
+        await expectError(openBallot(nullifier, committeeProposal), "TallyKeyPending");
+      });
+
+      it("only accepts a dealing signed by its member, once", async () => {
+        await expectError(deal(2, members[0]), "InvalidCommitteeMember");
+        await expectError(deal(0), "DealingAlreadySubmitted");
+      });
+
+      it("settles complaints by answer or disqualification", async () => {
+        await complain(1, 0);
+        await complain(2, 1);
+        await expectError(fixKey(), "ComplaintRoundOpen");
+
+        // Dealer 0 publishes member 1's share; dealer 1 stays silent
+        await expectError(answer(0, 1, 1n), "InvalidComplaintAnswer");
+        await answer(0, 1);
+        await expectError(answer(0, 1), "NoComplaint");
+
+        const committee = await program.account.decryptionCommittee.fetch(committeePda);
+        await waitUntil(committee.complaintsEndAt.toNumber());
+        await expectError(complain(1, 2), "ComplaintRoundEnded");
+        await expectError(fixKey(), "ComplaintsOutstanding");
+        await expectError(disqualify(1), "ComplaintRoundOpen");
+
+        await waitUntil(committee.answersEndAt.toNumber());
+        await expectError(disqualify(0), "NoComplaint");
+        await disqualify(1);
+        await fixKey();
+      });
+
+      it("sets the tally key to the sum of the qualified dealings", async () => {
+        const proposal = await program.account.proposal.fetch(committeeProposal);
+        expect(Buffer.from(proposal.ballotMode.encrypted.key).toString("hex")).to.equal(
+          pointBytes(tallyKey).toString("hex")
+        );
+        expect(proposal.committee.toBase58()).to.equal(committeePda.toBase58());
+        await expectError(fixKey(), "TallyKeyAlreadySet");
+      });
+
+      it("decrypts the tally from a threshold of shares only", async () => {
+        const randomness = await postBallot(nullifier, [0, 1], committeeProposal, tallyKey);
+        await castEncrypted(nullifier, randomness, committeeProposal, tallyKey);
+        await waitUntil(committeeEndsAt);
+
+        // The key's secret is never held by anyone, and decrypt_tally is off
+        await expectError(
+          program.methods
+            .decryptTally([new BN(0), new BN(1)], [])
+            .accountsPartial({
+              proposal: committeeProposal,
+              encryptedTally: findEncryptedTallyPda(committeeProposal),
+            })
+            .rpc(),
+          "CommitteeDecryptionRequired"
+        );
+
+        await submitShares(0);
+        await expectError(combine([0, 1]), "NotEnoughShares");
+        await submitShares(2);
+        await expectError(combine([1, 0]), "InvalidTally");
+        await combine([0, 1]);
+
+        await finalize(committeeProposal, findEncryptedTallyPda(committeeProposal));
+        const proposal = await program.account.proposal.fetch(committeeProposal);
+        expect(proposal.winningOption).to.equal(1);
+      });
+    });
+  });
+
+  describe("sealed ballots", () => {
+    let sealedPda: PublicKey;
+    let votingEndsAt: number;
+    let revealEndsAt: number;
+    // Two votes for option 1 are revealed, the vote for option 0 never is
+    const votes = [1, 1, 0];
+    // Salts are BN254 scalars, which Poseidon takes as field elements
+    const salts = votes.map(() => randomScalar());
+    const nullifiers = votes.map((_, i) => filled(0xb0 + i));
+
+    function findSealedBallotPda(nullifier: Buffer, group = 0, proposal = sealedPda): PublicKey {
+      return PublicKey.findProgramAddressSync(
+        [
+          Buffer.from("sealed_ballot"),
+          proposal.toBuffer(),
+          Buffer.from([group]),
+          nullifier,
+        ],
+        program.programId
+      )[0];
+    }
+
+    function castSealed(
+      nullifier: Buffer,
+      commitment: bigint,
+      encryptedOpening = Buffer.alloc(0),
+      proposal = sealedPda
+    ) {
+      return program.methods
+        .castSealedVote(
+          Array.from(nullifier),
+          fieldBytes(commitment),
+          mockProof,
+          null,
+          0,
+          new BN(0),
+          encryptedOpening
+        )
+        .accountsPartial({
+          proposal,
+          nullifierAccount: findNullifierPda(proposal, nullifier),
+          ballot: findSealedBallotPda(nullifier, 0, proposal),
+          prerequisite: null,
+          registry: null,
+          payer: authority.publicKey,
+          config: configPda,
+          systemProgram: SystemProgram.programId,
+        })
+        .rpc();
+    }
+
+    function reveal(index: number, vote = votes[index], salt = salts[index], group = 0) {
+      return program.methods
+        .revealVote(Array.from(nullifiers[index]), group, vote, fieldBytes(salt))
+        .accountsPartial({
+          proposal: sealedPda,
+          ballot: findSealedBallotPda(nullifiers[index], group),
+        })
+        .rpc();
+    }
+
+    before(async () => {
+      votingEndsAt = now() + 10;
+      revealEndsAt = votingEndsAt + 8;
+      sealedPda = await createProposal(new BN(2500), {
+        votingEndsAt: new BN(votingEndsAt),
+        ballotMode: { commitReveal: { revealEndsAt: new BN(revealEndsAt) } },
+      });
+      for (let i = 0; i < votes.length; i++) {
+        await castSealed(nullifiers[i], poseidonHash(BigInt(votes[i]), salts[i]));
+      }
+    });
+
+    it("keeps the tally sealed while voting is open", async () => {
+      const proposal = await program.account.proposal.fetch(sealedPda);
+      expect(proposal.totalVotes.toNumber()).to.equal(3);
+      expect(proposal.unrevealedVotes.toNumber()).to.equal(3);
+      expect(proposal.voteCounts.slice(0, 2).map((count) => count.toNumber())).to.deep.equal([
+        0, 0,
+      ]);
+    });
+
+    it("rejects an encrypted opening on a commit-reveal proposal", async () => {
+      await expectError(
+        castSealed(filled(0xbf), 1n, Buffer.alloc(32, 1)),
+        "InvalidEncryptedOpening"
+      );
+    });
+
+    it("does not open the reveal window before voting ends", async () => {
+      await expectError(reveal(0), "VotingNotEnded");
+    });
+
+    it("reveals ballots during the window", async () => {
+      await waitUntil(votingEndsAt);
+      await expectError(reveal(0, 0), "RevealMismatch");
+      // The ballot is keyed by its voter group as well as its nullifier
+      await expectError(reveal(0, votes[0], salts[0], 1), "AccountNotInitialized");
+
+      await reveal(0);
+      await reveal(1);
+      await expectError(reveal(1), "BallotAlreadyRevealed");
+      await expectError(finalize(sealedPda), "RevealWindowOpen");
+
+      const proposal = await program.account.proposal.fetch(sealedPda);
+      expect(proposal.unrevealedVotes.toNumber()).to.equal(1);
+      expect(proposal.voteCounts.slice(0, 2).map((count) => count.toNumber())).to.deep.equal([
+        0, 2,
+      ]);
+    });
+
+    it("closes the reveal window at reveal_ends_at", async () => {
+      await waitUntil(revealEndsAt);
+      await expectError(reveal(2), "RevealWindowClosed");
+    });
+
+    it("leaves unrevealed ballots out of the outcome and turnout", async () => {
+      await finalize(sealedPda);
+      const proposal = await program.account.proposal.fetch(sealedPda);
+      expect(proposal.winningOption).to.equal(1);
+      expect(proposal.unrevealedVotes.toNumber()).to.equal(1);
+      // 2 counted ballots of 10 eligible voters
+      expect(proposal.turnoutBps).to.equal(2000);
+    });
+
+    describe("hidden tally", () => {
+      let hiddenPda: PublicKey;
+      let hiddenEndsAt: number;
+      // The first ballot is revealed by its voter, the second by the key holder
+      const hiddenNullifiers = [filled(0xb8), filled(0xb9)];
+      const hiddenSalts = hiddenNullifiers.map(() => randomScalar());
+
+      before(async () => {
+        hiddenEndsAt = now() + 6;
+        hiddenPda = await createProposal(new BN(2501), {
+          votingEndsAt: new BN(hiddenEndsAt),
+          ballotMode: {
+            hiddenTally: {
+              revealEndsAt: new BN(hiddenEndsAt + 3600),
+              tallyKey: Array(32).fill(7),
+            },
+          },
+        });
+        // The escrowed opening is opaque to the program
+        for (let i = 0; i < hiddenNullifiers.length; i++) {
+          await castSealed(
+            hiddenNullifiers[i],
+            poseidonHash(1n, hiddenSalts[i]),
+            Buffer.alloc(64, 1),
+            hiddenPda
+          );
+        }
+      });
+
+      it("requires an encrypted opening", async () => {
+        await expectError(
+          castSealed(filled(0xbf), 1n, Buffer.alloc(0), hiddenPda),
+          "InvalidEncryptedOpening"
+        );
+      });
+
+      it("keeps vote_counts at zero until voting ends", async () => {
+        const proposal = await program.account.proposal.fetch(hiddenPda);
+        expect(proposal.unrevealedVotes.toNumber()).to.equal(2);
+        expect(proposal.voteCounts[1].toNumber()).to.equal(0);
+        await expectError(
+          program.methods
+            .revealTally([])
+            .accountsPartial({ proposal: hiddenPda })
+            .rpc(),
+          "VotingNotEnded"
+        );
+      });
+
+      it("lets anyone holding an opening reveal", async () => {
+        await waitUntil(hiddenEndsAt);
+        await program.methods
+          .revealVote(Array.from(hiddenNullifiers[0]), 0, 1, fieldBytes(hiddenSalts[0]))
+          .accountsPartial({
+            proposal: hiddenPda,
+            ballot: findSealedBallotPda(hiddenNullifiers[0], 0, hiddenPda),
+          })
+          .rpc();
+
+        // Already revealed ballots are skipped by the crank
+        await program.methods
+          .revealTally(hiddenSalts.map((salt) => ({ vote: 1, salt: fieldBytes(salt) })))
+          .accountsPartial({ proposal: hiddenPda })
+          .remainingAccounts(
+            hiddenNullifiers.map((nullifier) => ({
+              pubkey: findSealedBallotPda(nullifier, 0, hiddenPda),
+              isSigner: false,
+              isWritable: true,
+            }))
+          )
+          .rpc();
+
+        const proposal = await program.account.proposal.fetch(hiddenPda);
+        expect(proposal.unrevealedVotes.toNumber()).to.equal(0);
+        expect(proposal.voteCounts[1].toNumber()).to.equal(2);
+      });
+    });
+  });
+
+  describe("vote revision", () => {
+    const noVotes = Array(8).fill(0);
+
+    function reviseVote(
+      proposal: PublicKey,
+      nullifier: Buffer,
+      revision: number,
+      vote: number,
+      votes: number[] = noVotes
+    ) {
+      return program.methods
+        .reviseVote(Array.from(nullifier), vote, votes, mockProof, null, 0, new BN(0), revision)
+        .accountsPartial({
+          proposal,
+          nullifierAccount: findNullifierPda(proposal, nullifier),
+          prerequisite: null,
+          registry: null,
+          payer: authority.publicKey,
+          config: configPda,
+        })
+        .rpc();
+    }
+
+    async function counts(proposal: PublicKey, numOptions: number): Promise<number[]> {
+      const account = await program.account.proposal.fetch(proposal);
+      return account.voteCounts.slice(0, numOptions).map((count) => count.toNumber());
+    }
+
+    it("moves a single-choice ballot to the new option", async () => {
+      const proposal = await createProposal(new BN(2600), { allowRevision: true });
+      await castVote(proposal, filled(0xe8), 0);
+      await castVote(proposal, filled(0xe9), 0);
+
+      await reviseVote(proposal, filled(0xe8), 1, 1);
+      expect(await counts(proposal, 2)).to.deep.equal([1, 1]);
+
+      // Revising back only touches the revised ballot's own vote
+      await reviseVote(proposal, filled(0xe8), 2, 0);
+      expect(await counts(proposal, 2)).to.deep.equal([2, 0]);
+      const nullifier = await program.account.nullifierAccount.fetch(
+        findNullifierPda(proposal, filled(0xe8))
+      );
+      expect(nullifier.revisions).to.equal(2);
+    });
+
+    it("rejects a revision that isn't the next one", async () => {
+      const proposal = findProposalPda(new BN(2600));
+      // Replaying revision 1 would bring its ballot back
+      await expectError(reviseVote(proposal, filled(0xe8), 1, 1), "StaleRevision");
+      await expectError(reviseVote(proposal, filled(0xe8), 4, 1), "StaleRevision");
+    });
+
+    it("replaces every option of an approval ballot", async () => {
+      const proposal = await createProposal(new BN(2601), {
+        numOptions: 3,
+        ballotMode: { approval: {} },
+        allowRevision: true,
+      });
+      await castVote(proposal, filled(0xea), 0b011);
+      expect(await counts(proposal, 3)).to.deep.equal([1, 1, 0]);
+
+      await reviseVote(proposal, filled(0xea), 1, 0b110);
+      expect(await counts(proposal, 3)).to.deep.equal([0, 1, 1]);
+    });
+
+    it("replaces the whole vector of a points ballot", async () => {
+      const proposal = await createProposal(new BN(2602), {
+        numOptions: 3,
+        ballotMode: { points: { budget: 10 } },
+        allowRevision: true,
+      });
+      await castVectorVote(proposal, filled(0xeb), [6, 4, 0, 0, 0, 0, 0, 0]);
+      expect(await counts(proposal, 3)).to.deep.equal([6, 4, 0]);
+
+      await reviseVote(proposal, filled(0xeb), 1, 0, [0, 3, 7, 0, 0, 0, 0, 0]);
+      expect(await counts(proposal, 3)).to.deep.equal([0, 3, 7]);
+    });
+
+    it("only revises on proposals that allow it", async () => {
+      const proposal = await createProposal(new BN(2603));
+      await castVote(proposal, filled(0xec), 0);
+      await expectError(reviseVote(proposal, filled(0xec), 1, 1), "RevisionNotAllowed");
+    });
+  });
 });
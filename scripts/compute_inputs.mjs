@@ -12,8 +12,9 @@ async function main() {
     const leaf = poseidon.F.toString(poseidon([secret, secret]));
     console.log("leaf =", leaf);
 
-    // Compute nullifier = hash(secret, proposal_id)
-    const nullifier = poseidon.F.toString(poseidon([secret, proposal_id]));
+    // Compute nullifier = hash(secret, proposal_id, group), outside voter groups
+    const group = BigInt(0);
+    const nullifier = poseidon.F.toString(poseidon([secret, proposal_id, group]));
     console.log("nullifier =", nullifier);
 
     // Compute voters_root with all-zero siblings (single voter at leftmost)
@@ -47,6 +48,7 @@ async function main() {
     console.log(`vote = "1"`);
     console.log(`nullifier = "${nullifier}"`);
     console.log(`voters_root = "${current.toString()}"`);
+    console.log(`group = "${group}"`);
 }
 
 main().catch(console.error);
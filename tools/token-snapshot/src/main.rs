@@ -0,0 +1,320 @@
+//! Token-holder snapshot for TokenSnapshot voter registries
+//!
+//! Reads every SPL token account of a mint at the requested finalized slot,
+//! sums balances per owner, keeps owners holding at least the threshold and
+//! builds the Poseidon tree whose root goes into `Eligibility::TokenSnapshot`.
+//!
+//! RPC nodes only serve current state, so the snapshot is taken at the node's
+//! finalized slot, which must be at least `--slot`. If it moved past the
+//! requested slot the tool fails, since the registry would record a slot the
+//! balances were not read at; `--allow-slot-drift` accepts the newer slot
+//! with a warning and writes that slot to the output instead.
+//!
+//! Leaves are Poseidon(wallet[0..16], wallet[16..32], balance) and nodes are
+//! Poseidon(left, right), matching `verify_holder_proof` in the program. The
+//! output JSON carries the root, slot and one `HolderProof` per holder.
+//!
+//! Usage:
+//!   token-snapshot --rpc <url> --mint <pubkey> --slot <u64> --min-amount <u64>
+//!                  [--allow-slot-drift] [--out <file>]
+
+use std::collections::BTreeMap;
+use std::error::Error;
+
+use ark_bn254::Fr;
+use base64::Engine;
+use light_poseidon::{Poseidon, PoseidonBytesHasher};
+use serde_json::{json, Value};
+
+const TOKEN_PROGRAM_ID: &str = "TokenkegQfeZyiNwAJbNbGKPFXCWuYvf9Ss623VQ5DA";
+const TOKEN_ACCOUNT_LEN: u64 = 165;
+
+type Result<T> = std::result::Result<T, Box<dyn Error>>;
+
+/// (owner wallet, token amount)
+type Holding = ([u8; 32], u64);
+
+struct Args {
+    rpc: String,
+    mint: String,
+    slot: u64,
+    allow_slot_drift: bool,
+    min_amount: u64,
+    out: Option<String>,
+}
+
+fn parse_args() -> Result<Args> {
+    let mut rpc = "https://api.devnet.solana.com".to_string();
+    let mut mint = None;
+    let mut slot = None;
+    let mut allow_slot_drift = false;
+    let mut min_amount = 1;
+    let mut out = None;
+
+    let mut args = std::env::args().skip(1);
+    while let Some(flag) = args.next() {
+        let mut value = || args.next().ok_or(format!("missing value for {flag}"));
+        match flag.as_str() {
+            "--rpc" => rpc = value()?,
+            "--mint" => mint = Some(value()?),
+            "--slot" => slot = Some(value()?.parse()?),
+            "--allow-slot-drift" => allow_slot_drift = true,
+            "--min-amount" => min_amount = value()?.parse()?,
+            "--out" => out = Some(value()?),
+            _ => return Err(format!("unknown argument {flag}").into()),
+        }
+    }
+
+    Ok(Args {
+        rpc,
+        mint: mint.ok_or("--mint is required")?,
+        slot: slot.ok_or("--slot is required")?,
+        allow_slot_drift,
+        min_amount,
+        out,
+    })
+}
+
+/// Fetch all token accounts of `mint` from a node that has reached
+/// `min_slot`; returns the context slot and (owner, amount) pairs
+fn fetch_token_accounts(rpc: &str, mint: &str, min_slot: u64) -> Result<(u64, Vec<Holding>)> {
+    let request = json!({
+        "jsonrpc": "2.0",
+        "id": 1,
+        "method": "getProgramAccounts",
+        "params": [
+            TOKEN_PROGRAM_ID,
+            {
+                "commitment": "finalized",
+                "encoding": "base64",
+                "withContext": true,
+                "minContextSlot": min_slot,
+                "filters": [
+                    { "dataSize": TOKEN_ACCOUNT_LEN },
+                    { "memcmp": { "offset": 0, "bytes": mint } }
+                ]
+            }
+        ]
+    });
+
+    let response: Value = ureq::post(rpc).send_json(request)?.into_json()?;
+    if let Some(error) = response.get("error") {
+        return Err(format!("RPC error: {error}").into());
+    }
+
+    let result = &response["result"];
+    let slot = result["context"]["slot"]
+        .as_u64()
+        .ok_or("RPC response missing context slot")?;
+
+    let mut accounts = Vec::new();
+    for entry in result["value"]
+        .as_array()
+        .ok_or("RPC response missing accounts")?
+    {
+        let encoded = entry["account"]["data"][0]
+            .as_str()
+            .ok_or("token account data is not base64")?;
+        let data = base64::engine::general_purpose::STANDARD.decode(encoded)?;
+
+        // SPL token account layout: mint (32) | owner (32) | amount (u64 LE) | ...
+        let owner: [u8; 32] = data[32..64].try_into()?;
+        let amount = u64::from_le_bytes(data[64..72].try_into()?);
+        accounts.push((owner, amount));
+    }
+
+    Ok((slot, accounts))
+}
+
+fn poseidon(inputs: &[[u8; 32]]) -> Result<[u8; 32]> {
+    let mut hasher = Poseidon::<Fr>::new_circom(inputs.len())?;
+    let inputs: Vec<&[u8]> = inputs.iter().map(|input| input.as_slice()).collect();
+    Ok(hasher.hash_bytes_be(&inputs)?)
+}
+
+fn holder_leaf(wallet: &[u8; 32], balance: u64) -> Result<[u8; 32]> {
+    let mut wallet_hi = [0u8; 32];
+    wallet_hi[16..].copy_from_slice(&wallet[..16]);
+    let mut wallet_lo = [0u8; 32];
+    wallet_lo[16..].copy_from_slice(&wallet[16..]);
+    let mut balance_bytes = [0u8; 32];
+    balance_bytes[24..].copy_from_slice(&balance.to_be_bytes());
+    poseidon(&[wallet_hi, wallet_lo, balance_bytes])
+}
+
+/// Sibling hashes from leaf `index` up to the root
+fn proof_siblings(levels: &[Vec<[u8; 32]>], index: usize) -> Vec<[u8; 32]> {
+    levels[..levels.len() - 1]
+        .iter()
+        .enumerate()
+        .map(|(depth, level)| level[(index >> depth) ^ 1])
+        .collect()
+}
+
+/// Build every level of the tree, leaves first, padding with zero leaves to a
+/// power of two
+fn build_levels(mut leaves: Vec<[u8; 32]>) -> Result<Vec<Vec<[u8; 32]>>> {
+    leaves.resize(leaves.len().next_power_of_two(), [0u8; 32]);
+
+    let mut levels = vec![leaves];
+    while levels.last().unwrap().len() > 1 {
+        let next = levels
+            .last()
+            .unwrap()
+            .chunks(2)
+            .map(|pair| poseidon(&[pair[0], pair[1]]))
+            .collect::<Result<Vec<_>>>()?;
+        levels.push(next);
+    }
+    Ok(levels)
+}
+
+fn main() -> Result<()> {
+    let args = parse_args()?;
+    bs58::decode(&args.mint).into_vec()?;
+
+    eprintln!("Fetching token accounts for {} ...", args.mint);
+    let (slot, accounts) = fetch_token_accounts(&args.rpc, &args.mint, args.slot)?;
+    if slot != args.slot {
+        if !args.allow_slot_drift {
+            return Err(format!(
+                "RPC answered at slot {slot}, not the requested slot {}; \
+                 rerun with --slot {slot} or pass --allow-slot-drift",
+                args.slot
+            )
+            .into());
+        }
+        eprintln!(
+            "warning: balances read at slot {slot}, not the requested slot {}; \
+             register the snapshot with slot {slot}",
+            args.slot
+        );
+    }
+
+    // One wallet may own several token accounts of the same mint
+    let mut balances: BTreeMap<[u8; 32], u64> = BTreeMap::new();
+    for (owner, amount) in accounts {
+        let balance = balances.entry(owner).or_default();
+        *balance = balance.saturating_add(amount);
+    }
+    let holders: Vec<Holding> = balances
+        .into_iter()
+        .filter(|(_, balance)| *balance >= args.min_amount)
+        .collect();
+    if holders.is_empty() {
+        return Err("no holders at or above the threshold".into());
+    }
+
+    let leaves = holders
+        .iter()
+        .map(|(wallet, balance)| holder_leaf(wallet, *balance))
+        .collect::<Result<Vec<_>>>()?;
+    let levels = build_levels(leaves)?;
+    let root = levels.last().unwrap()[0];
+
+    let holders_json: Vec<Value> = holders
+        .iter()
+        .enumerate()
+        .map(|(index, (wallet, balance))| {
+            let siblings: Vec<String> = proof_siblings(&levels, index)
+                .iter()
+                .map(hex::encode)
+                .collect();
+            json!({
+                "wallet": bs58::encode(wallet).into_string(),
+                "balance": balance.to_string(),
+                "index": index,
+                "siblings": siblings,
+            })
+        })
+        .collect();
+
+    let snapshot = json!({
+        "mint": args.mint,
+        "slot": slot,
+        "minAmount": args.min_amount.to_string(),
+        "holdersRoot": hex::encode(root),
+        "holders": holders_json,
+    });
+
+    let output = serde_json::to_string_pretty(&snapshot)?;
+    match &args.out {
+        Some(path) => std::fs::write(path, output)?,
+        None => println!("{output}"),
+    }
+
+    eprintln!("Slot:         {slot}");
+    eprintln!("Holders:      {}", holders.len());
+    eprintln!("Holders root: 0x{}", hex::encode(root));
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Fold a leaf up its path like `compute_merkle_root` in the program
+    fn merkle_root(leaf: [u8; 32], mut index: usize, siblings: &[[u8; 32]]) -> [u8; 32] {
+        let mut current = leaf;
+        for sibling in siblings {
+            current = if index & 1 == 0 {
+                poseidon(&[current, *sibling]).unwrap()
+            } else {
+                poseidon(&[*sibling, current]).unwrap()
+            };
+            index >>= 1;
+        }
+        current
+    }
+
+    #[test]
+    fn poseidon_matches_program_zero_hash() {
+        // ZERO_HASHES[1] in the program
+        assert_eq!(
+            hex::encode(poseidon(&[[0u8; 32], [0u8; 32]]).unwrap()),
+            "2098f5fb9e239eab3ceac3f27b81e481dc3124d55ffed523a839ee8446b64864"
+        );
+    }
+
+    #[test]
+    fn holder_proofs_verify_against_root() {
+        let holders: Vec<Holding> = (1u8..=5).map(|i| ([i; 32], u64::from(i) * 1_000)).collect();
+        let leaves = holders
+            .iter()
+            .map(|(wallet, balance)| holder_leaf(wallet, *balance).unwrap())
+            .collect();
+        let levels = build_levels(leaves).unwrap();
+        let root = levels.last().unwrap()[0];
+
+        // 5 holders pad to 8 leaves: 3 siblings per proof
+        assert_eq!(levels.len(), 4);
+        for (index, (wallet, balance)) in holders.iter().enumerate() {
+            let siblings = proof_siblings(&levels, index);
+            assert_eq!(siblings.len(), 3);
+            let leaf = holder_leaf(wallet, *balance).unwrap();
+            assert_eq!(merkle_root(leaf, index, &siblings), root);
+
+            // A different balance must not verify
+            let forged = holder_leaf(wallet, balance + 1).unwrap();
+            assert_ne!(merkle_root(forged, index, &siblings), root);
+        }
+    }
+
+    #[test]
+    fn holder_leaf_splits_wallet_into_halves() {
+        let mut wallet = [0u8; 32];
+        wallet[..16].fill(0x11);
+        wallet[16..].fill(0x22);
+        let mut hi = [0u8; 32];
+        hi[16..].fill(0x11);
+        let mut lo = [0u8; 32];
+        lo[16..].fill(0x22);
+        let mut balance = [0u8; 32];
+        balance[24..].copy_from_slice(&42u64.to_be_bytes());
+
+        assert_eq!(
+            holder_leaf(&wallet, 42).unwrap(),
+            poseidon(&[hi, lo, balance]).unwrap()
+        );
+    }
+}